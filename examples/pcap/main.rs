@@ -4,28 +4,26 @@ fn main() {
 
 #[cfg(test)]
 mod tests {
-    use std::{io::BufWriter, path::PathBuf};
+    use std::path::PathBuf;
 
     use pdl_compiler::ast::SourceDatabase;
     use pdl_dissector::Args;
 
     #[test]
     fn golden_file() {
-        let mut writer = BufWriter::new(Vec::new());
-        let mut sources = SourceDatabase::new();
-        pdl_dissector::run(
+        let dir = PathBuf::from(file!()).parent().unwrap().to_path_buf();
+        let mut diff = Vec::new();
+        let (up_to_date, _) = pdl_dissector::run_check(
             Args {
-                pdl_file: PathBuf::from(file!()).parent().unwrap().join("pcap.pdl"),
+                pdl_file: Some(dir.join("pcap.pdl")),
                 target_packets: vec![String::from("PcapFile")],
+                ..Default::default()
             },
-            &mut sources,
-            &mut writer,
+            &dir.join("pcap_dissector.lua"),
+            &mut SourceDatabase::new(),
+            &mut diff,
         )
         .unwrap();
-        let dissector_output = writer.into_inner().unwrap();
-        pretty_assertions::assert_str_eq!(
-            include_str!("pcap_dissector.lua"),
-            std::str::from_utf8(&dissector_output).unwrap(),
-        );
+        assert!(up_to_date, "{}", String::from_utf8_lossy(&diff));
     }
 }
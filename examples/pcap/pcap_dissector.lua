@@ -1,16 +1,104 @@
+-- Generated by pdl-dissector 0.1.0. Do not edit, regenerate it instead.
+-- Inputs: sha256:2e396050ff68d7ef0854a09b7b0214f19ea31a6e2c48b4f98d81b9e3e804d450
 -- Utils section
-function enforce_len_limit(num, limit, tree)
+
+-- Raised when a field extends past the captured bytes, but not past the reported length of the
+-- frame, e.g. because the capture was taken with a small snaplen. Like the exceptions of the C
+-- dissectors, it stops the dissection of the whole packet, which is then shown as truncated rather
+-- than malformed.
+PACKET_TRUNCATED = setmetatable({}, {
+    __tostring = function() return "Packet size limited during capture" end,
+})
+
+-- Raises `PACKET_TRUNCATED` if a field starting at `offset` in `buffer` needs `needed_len` bytes
+-- past its captured bytes, but not past its reported length, e.g. because the capture was taken
+-- with a small snaplen. `needed_len` can be nil if it is unknown. Sub-buffers of fields with a
+-- known length are fully captured, so errors inside them are reported as malformed.
+function check_truncated(buffer, offset, needed_len)
+    if buffer:len() >= buffer:reported_len() then
+        return
+    end
+    if needed_len == nil or offset + needed_len <= buffer:reported_len() then
+        error(PACKET_TRUNCATED, 0)
+    end
+end
+
+-- Marks the packet in `tree` as truncated if `err` is `PACKET_TRUNCATED`, and raises `err` again
+-- otherwise. Returns the offset after the captured bytes.
+function report_truncated(tree, buffer, protocol_name, err)
+    if err ~= PACKET_TRUNCATED then
+        error(err, 0)
+    end
+    tree:add("[Packet size limited during capture: " .. protocol_name .. " truncated]")
+    tree:append_text(" [" .. buffer:len() .. " of " .. buffer:reported_len() .. " bytes captured]")
+    return buffer:len()
+end
+
+-- Returns `num`, the length of a field at the start of `buffer`, limited to the captured bytes.
+function enforce_len_limit(num, buffer, tree, experts)
+    local limit = buffer:len()
     if num == nil then
         return limit
     end
     if num > limit then
-        tree:add_expert_info(PI_MALFORMED, PI_ERROR,
+        check_truncated(buffer, 0, num)
+        tree:add_proto_expert_info(experts.length_overrun,
             "Expected " .. num .. " bytes, but only " .. limit .. " bytes remaining")
         return limit
     end
     return num
 end
 
+-- Returns the bytes of a field of `field_len` bytes at `i` to dissect it from. Fields with a
+-- declared length are limited to it, while the others keep the reported length of `buffer`, so
+-- that fields inside them that were cut off by the capture are reported as truncated.
+function field_buffer(buffer, i, field_len, declared_len)
+    if declared_len == nil then
+        return buffer(i)
+    end
+    return buffer(i, field_len)
+end
+
+-- Checks that a field starting at `start` with a declared length of `declared_len` bytes ended at
+-- `i`, and returns the offset after the field. Bytes that were declared but not dissected are added
+-- to `tree` as an undecoded item and skipped. Missing bytes are already reported by
+-- `enforce_len_limit` and the array loops, so they are not reported again.
+function check_consumed_len(tree, experts, buffer, size_name, declared_len, start, i)
+    local consumed_len = i - start
+    if declared_len == nil or consumed_len == declared_len then
+        return i
+    end
+    if consumed_len < declared_len and i >= buffer:len() then
+        return i
+    end
+    tree:add_proto_expert_info(experts.size_mismatch,
+        "Error: " .. size_name .. "=" .. declared_len .. " but " .. string.format("%g", consumed_len) .. " bytes consumed")
+    if consumed_len > declared_len then
+        return i
+    end
+    local undecoded_len = math.min(declared_len - consumed_len, buffer:len() - i)
+    tree:add(buffer(i, undecoded_len), "Undecoded (" .. string.format("%g", undecoded_len) .. " bytes)")
+    return i + undecoded_len
+end
+
+-- Reports an error raised while dissecting `field_name` of the declaration `decl_name`, and marks
+-- the rest of `buffer` from `i` as malformed. Returns the offset after the marked bytes.
+-- `PACKET_TRUNCATED` is raised again, so that it stops the dissection of the whole packet.
+function report_dissect_error(tree, experts, buffer, i, decl_name, field_name, err)
+    if err == PACKET_TRUNCATED then
+        error(err, 0)
+    end
+    local start = math.min(math.floor(i), buffer:len())
+    local malformed = tree:add(buffer(start, buffer:len() - start), "[Malformed]")
+    local location = decl_name
+    if field_name ~= nil then
+        location = decl_name .. "." .. field_name
+    end
+    malformed:add_proto_expert_info(experts.dissect_error,
+        "Error: Failed to dissect `" .. location .. "`: " .. tostring(err))
+    return buffer:len()
+end
+
 function sum_or_nil(...)
     local sum = 0
     local params = table.pack(...)
@@ -65,10 +153,12 @@ function AlignedProtoField:new(o)
         ftype = nil,
         valuestring = nil,
         base = nil,
+        unit = nil, -- optional
         is_little_endian = nil,
         description = nil, -- optional
     }
-    o.field = ProtoField.new(o.name, o.abbr, o.ftype, o.valuestring, o.base, nil, o.description)
+    local valuestring, field_base = get_valuestring_and_base(o, o.valuestring)
+    o.field = ProtoField.new(o.name, o.abbr, o.ftype, valuestring, field_base, nil, o.description)
     setmetatable(o, self)
     self.__index = self
     return o
@@ -93,9 +183,20 @@ function UnalignedProtoField:new(o)
         bitoffset = nil,
         bitlen = nil, -- optional
         valuestring = nil, -- optional
+        base = nil, -- optional
+        unit = nil, -- optional
         description = nil, -- optional
     }
-    o.field = ProtoField.new(o.name, o.abbr, o.ftype, nil, nil, nil, o.description)
+    if o.ftype == ftypes.BOOLEAN then
+        -- Booleans are registered with a bitmask over the bytes they span
+        local numbytes = math.ceil((o.bitoffset + o.bitlen) / 8)
+        o.value_shift = 2 ^ (numbytes * 8 - o.bitoffset - o.bitlen)
+        local mask = (2 ^ o.bitlen - 1) * o.value_shift
+        o.field = ProtoField.new(o.name, o.abbr, o.ftype, o.valuestring, numbytes * 8, mask, o.description)
+    else
+        local valuestring, field_base = get_valuestring_and_base(o, nil)
+        o.field = ProtoField.new(o.name, o.abbr, o.ftype, valuestring, field_base, nil, o.description)
+    end
     setmetatable(o, self)
     self.__index = self
     return o
@@ -114,11 +215,23 @@ function UnalignedProtoField:dissect(tree, buffer, runtime_len)
     label = label .. string.rep(".", numbytes * 8 - bitlen - self.bitoffset)
     label = format_bitstring(label) .. " = " .. self.name
     label = label .. ": " .. self:get_value_display_string(value) -- Print out the string label
-    local subtree = tree:add(self.field, buf, value, label)
+    local item_value = value
+    if self.value_shift ~= nil then
+        -- Wireshark applies the bitmask to the given value, so shift the value back into place
+        item_value = value * self.value_shift
+    end
+    local subtree = tree:add(self.field, buf, item_value, label)
     return subtree, value, bitlen
 end
 
 function UnalignedProtoField:get_value_display_string(value)
+    if self.ftype == ftypes.BOOLEAN and self.valuestring ~= nil then
+        if value ~= 0 then
+            return self.valuestring[1]
+        else
+            return self.valuestring[2]
+        end
+    end
     if self.valuestring ~= nil then
         for _, range in ipairs(self.valuestring) do
             if range[1] <= value and value <= range[2] then
@@ -126,7 +239,30 @@ function UnalignedProtoField:get_value_display_string(value)
             end
         end
     end
-    return value
+    return format_integer(value, self.base) .. nil_coalesce(self.unit, "")
+end
+
+-- Returns the valuestring and base to register a ProtoField with, which displays `o.unit` after
+-- the values if it is set
+function get_valuestring_and_base(o, valuestring)
+    if o.unit ~= nil then
+        return {o.unit}, nil_coalesce(o.base, base.DEC) + base.UNIT_STRING
+    end
+    return valuestring, o.base
+end
+
+-- Formats an integer in the given base, like Wireshark does
+function format_integer(value, display_base)
+    if display_base == base.HEX then
+        return string.format("0x%x", value)
+    elseif display_base == base.OCT then
+        return string.format("0%o", value)
+    elseif display_base == base.DEC_HEX then
+        return string.format("%d (0x%x)", value, value)
+    elseif display_base == base.HEX_DEC then
+        return string.format("0x%x (%d)", value, value)
+    end
+    return tostring(value)
 end
 
 ProtoEnum = {}
@@ -167,6 +303,22 @@ function ProtoEnum:match(enum_name, value)
     return false
 end
 
+-- Formats the value of a field for the Info column: the name of its tag if `enum` is given, or `?`
+-- if the field was not dissected.
+function info_value(value, enum)
+    if value == nil then
+        return "?"
+    end
+    if enum ~= nil then
+        for _, matcher in ipairs(enum.matchers) do
+            if matcher[1] <= value and value <= matcher[2] then
+                return matcher[3]
+            end
+        end
+    end
+    return tostring(value)
+end
+
 -- Add a space every 4 characters in the string
 -- Example: 0010010101 -> 0010 0101 01
 function format_bitstring(input)
@@ -243,41 +395,54 @@ function PcapHeader_protocol_fields(fields, path)
     })
 end
 -- Sequence: PcapHeader (7 fields, 0 children, 0 constraints)
-function PcapHeader_dissect(buffer, pinfo, tree, fields, path)
+function PcapHeader_dissect(buffer, pinfo, tree, fields, experts, path, field_values)
     local i = 0
-    local field_values = {}
-    -- Scalar: Fixed value
-    local field_len = enforce_len_limit(4, buffer(i):len(), tree)
-    subtree, field_values[path .. "._fixed_0"], bitlen = fields[path .. "._fixed_0"]:dissect(tree, buffer(i), field_len)
-    i = i + bitlen / 8
-    local value = field_values[path .. "._fixed_0"]
-    if not (value == 2712847316) then
-        subtree:add_expert_info(PI_MALFORMED, PI_WARN, "Error: Expected `value == 2712847316` where value=" .. tostring(value))
-    end
-    -- Scalar: version_major
-    local field_len = enforce_len_limit(2, buffer(i):len(), tree)
-    subtree, field_values[path .. ".version_major"], bitlen = fields[path .. ".version_major"]:dissect(tree, buffer(i), field_len)
-    i = i + bitlen / 8
-    -- Scalar: version_minor
-    local field_len = enforce_len_limit(2, buffer(i):len(), tree)
-    subtree, field_values[path .. ".version_minor"], bitlen = fields[path .. ".version_minor"]:dissect(tree, buffer(i), field_len)
-    i = i + bitlen / 8
-    -- Scalar: thiszone
-    local field_len = enforce_len_limit(4, buffer(i):len(), tree)
-    subtree, field_values[path .. ".thiszone"], bitlen = fields[path .. ".thiszone"]:dissect(tree, buffer(i), field_len)
-    i = i + bitlen / 8
-    -- Scalar: sigfigs
-    local field_len = enforce_len_limit(4, buffer(i):len(), tree)
-    subtree, field_values[path .. ".sigfigs"], bitlen = fields[path .. ".sigfigs"]:dissect(tree, buffer(i), field_len)
-    i = i + bitlen / 8
-    -- Scalar: snaplen
-    local field_len = enforce_len_limit(4, buffer(i):len(), tree)
-    subtree, field_values[path .. ".snaplen"], bitlen = fields[path .. ".snaplen"]:dissect(tree, buffer(i), field_len)
-    i = i + bitlen / 8
-    -- Scalar: network
-    local field_len = enforce_len_limit(4, buffer(i):len(), tree)
-    subtree, field_values[path .. ".network"], bitlen = fields[path .. ".network"]:dissect(tree, buffer(i), field_len)
-    i = i + bitlen / 8
+    field_values = field_values or {}
+    local current_field = nil
+    local ok, err = pcall(function()
+        current_field = "Fixed value"
+        -- Scalar: Fixed value
+        local field_len = enforce_len_limit(4, buffer(i), tree, experts)
+        subtree, field_values[path .. "._fixed_0"], bitlen = fields[path .. "._fixed_0"]:dissect(tree, buffer(i), field_len)
+        i = i + bitlen / 8
+        local value = field_values[path .. "._fixed_0"]
+        if not (value == 2712847316) then
+            subtree:add_proto_expert_info(experts.fixed_mismatch, "Error: Expected `value == 2712847316` where value=" .. tostring(value))
+        end
+        current_field = "version_major"
+        -- Scalar: version_major
+        local field_len = enforce_len_limit(2, buffer(i), tree, experts)
+        subtree, field_values[path .. ".version_major"], bitlen = fields[path .. ".version_major"]:dissect(tree, buffer(i), field_len)
+        i = i + bitlen / 8
+        current_field = "version_minor"
+        -- Scalar: version_minor
+        local field_len = enforce_len_limit(2, buffer(i), tree, experts)
+        subtree, field_values[path .. ".version_minor"], bitlen = fields[path .. ".version_minor"]:dissect(tree, buffer(i), field_len)
+        i = i + bitlen / 8
+        current_field = "thiszone"
+        -- Scalar: thiszone
+        local field_len = enforce_len_limit(4, buffer(i), tree, experts)
+        subtree, field_values[path .. ".thiszone"], bitlen = fields[path .. ".thiszone"]:dissect(tree, buffer(i), field_len)
+        i = i + bitlen / 8
+        current_field = "sigfigs"
+        -- Scalar: sigfigs
+        local field_len = enforce_len_limit(4, buffer(i), tree, experts)
+        subtree, field_values[path .. ".sigfigs"], bitlen = fields[path .. ".sigfigs"]:dissect(tree, buffer(i), field_len)
+        i = i + bitlen / 8
+        current_field = "snaplen"
+        -- Scalar: snaplen
+        local field_len = enforce_len_limit(4, buffer(i), tree, experts)
+        subtree, field_values[path .. ".snaplen"], bitlen = fields[path .. ".snaplen"]:dissect(tree, buffer(i), field_len)
+        i = i + bitlen / 8
+        current_field = "network"
+        -- Scalar: network
+        local field_len = enforce_len_limit(4, buffer(i), tree, experts)
+        subtree, field_values[path .. ".network"], bitlen = fields[path .. ".network"]:dissect(tree, buffer(i), field_len)
+        i = i + bitlen / 8
+    end)
+    if not ok then
+        i = report_dissect_error(tree, experts, buffer, i, "PcapHeader", current_field, err)
+    end
     return i
 end
 function PcapHeader_match_constraints(field_values, path)
@@ -326,150 +491,99 @@ function PcapRecord_protocol_fields(fields, path)
     })
 end
 -- Sequence: PcapRecord (5 fields, 0 children, 0 constraints)
-function PcapRecord_dissect(buffer, pinfo, tree, fields, path)
+function PcapRecord_dissect(buffer, pinfo, tree, fields, experts, path, field_values)
     local i = 0
-    local field_values = {}
-    -- Scalar: ts_sec
-    local field_len = enforce_len_limit(4, buffer(i):len(), tree)
-    subtree, field_values[path .. ".ts_sec"], bitlen = fields[path .. ".ts_sec"]:dissect(tree, buffer(i), field_len)
-    i = i + bitlen / 8
-    -- Scalar: ts_usec
-    local field_len = enforce_len_limit(4, buffer(i):len(), tree)
-    subtree, field_values[path .. ".ts_usec"], bitlen = fields[path .. ".ts_usec"]:dissect(tree, buffer(i), field_len)
-    i = i + bitlen / 8
-    -- Scalar: Size(Payload)
-    local field_len = enforce_len_limit(4, buffer(i):len(), tree)
-    subtree, field_values[path .. "._payload__size"], bitlen = fields[path .. "._payload__size"]:dissect(tree, buffer(i), field_len)
-    i = i + bitlen / 8
-    -- Scalar: orig_len
-    local field_len = enforce_len_limit(4, buffer(i):len(), tree)
-    subtree, field_values[path .. ".orig_len"], bitlen = fields[path .. ".orig_len"]:dissect(tree, buffer(i), field_len)
-    i = i + bitlen / 8
-    -- Payload: Payload
-    local field_len = enforce_len_limit(sum_or_nil(0, field_values[path .. "._payload__size"]), buffer(i):len(), tree)
-    subtree, field_values[path .. "._payload_"], bitlen = fields[path .. "._payload_"]:dissect(tree, buffer(i), field_len)
-    i = i + bitlen / 8
+    field_values = field_values or {}
+    local current_field = nil
+    local ok, err = pcall(function()
+        current_field = "ts_sec"
+        -- Scalar: ts_sec
+        local field_len = enforce_len_limit(4, buffer(i), tree, experts)
+        subtree, field_values[path .. ".ts_sec"], bitlen = fields[path .. ".ts_sec"]:dissect(tree, buffer(i), field_len)
+        i = i + bitlen / 8
+        current_field = "ts_usec"
+        -- Scalar: ts_usec
+        local field_len = enforce_len_limit(4, buffer(i), tree, experts)
+        subtree, field_values[path .. ".ts_usec"], bitlen = fields[path .. ".ts_usec"]:dissect(tree, buffer(i), field_len)
+        i = i + bitlen / 8
+        current_field = "Size(Payload)"
+        -- Scalar: Size(Payload)
+        local field_len = enforce_len_limit(4, buffer(i), tree, experts)
+        subtree, field_values[path .. "._payload__size"], bitlen = fields[path .. "._payload__size"]:dissect(tree, buffer(i), field_len)
+        i = i + bitlen / 8
+        current_field = "orig_len"
+        -- Scalar: orig_len
+        local field_len = enforce_len_limit(4, buffer(i), tree, experts)
+        subtree, field_values[path .. ".orig_len"], bitlen = fields[path .. ".orig_len"]:dissect(tree, buffer(i), field_len)
+        i = i + bitlen / 8
+        current_field = "Payload"
+        -- Payload: Payload
+        local field_len = enforce_len_limit(sum_or_nil(0, field_values[path .. "._payload__size"]), buffer(i), tree, experts)
+        subtree, field_values[path .. "._payload_"], bitlen = fields[path .. "._payload_"]:dissect(tree, buffer(i), field_len)
+        i = i + bitlen / 8
+    end)
+    if not ok then
+        i = report_dissect_error(tree, experts, buffer, i, "PcapRecord", current_field, err)
+    end
     return i
 end
 function PcapRecord_match_constraints(field_values, path)
     return true
 end
 function PcapFile_protocol_fields(fields, path)
-    fields[path .. "._fixed_0"] = AlignedProtoField:new({
-        name = "Fixed value",
-        abbr = path .. "._fixed_0",
-        ftype = ftypes.UINT32,
-        bitlen = 32,
-        is_little_endian = true,
-        description = "magic number",
-    })
-    fields[path .. ".version_major"] = AlignedProtoField:new({
-        name = "version_major",
-        abbr = path .. ".version_major",
-        ftype = ftypes.UINT16,
-        bitlen = 16,
-        is_little_endian = true,
-        description = nil,
-    })
-    fields[path .. ".version_minor"] = AlignedProtoField:new({
-        name = "version_minor",
-        abbr = path .. ".version_minor",
-        ftype = ftypes.UINT16,
-        bitlen = 16,
-        is_little_endian = true,
-        description = nil,
-    })
-    fields[path .. ".thiszone"] = AlignedProtoField:new({
-        name = "thiszone",
-        abbr = path .. ".thiszone",
-        ftype = ftypes.UINT32,
-        bitlen = 32,
-        is_little_endian = true,
-        description = "GMT to local correction",
-    })
-    fields[path .. ".sigfigs"] = AlignedProtoField:new({
-        name = "sigfigs",
-        abbr = path .. ".sigfigs",
-        ftype = ftypes.UINT32,
-        bitlen = 32,
-        is_little_endian = true,
-        description = "accuracy of timestamps",
-    })
-    fields[path .. ".snaplen"] = AlignedProtoField:new({
-        name = "snaplen",
-        abbr = path .. ".snaplen",
-        ftype = ftypes.UINT32,
-        bitlen = 32,
-        is_little_endian = true,
-        description = "max length of captured packets, in octets",
-    })
-    fields[path .. ".network"] = AlignedProtoField:new({
-        name = "network",
-        abbr = path .. ".network",
-        ftype = ftypes.UINT32,
-        bitlen = 32,
-        is_little_endian = true,
-        description = "data link type",
-    })
-    fields[path .. ".ts_sec"] = AlignedProtoField:new({
-        name = "ts_sec",
-        abbr = path .. ".ts_sec",
+    PcapHeader_protocol_fields(fields, path .. ".header")
+    PcapRecord_protocol_fields(fields, path .. ".records")
+    fields[path .. ".records_item_count"] = AlignedProtoField:new({
+        name = "Item count",
+        abbr = path .. ".records_item_count",
         ftype = ftypes.UINT32,
-        bitlen = 32,
-        is_little_endian = true,
-        description = "timestamp seconds",
-    })
-    fields[path .. ".ts_usec"] = AlignedProtoField:new({
-        name = "ts_usec",
-        abbr = path .. ".ts_usec",
-        ftype = ftypes.UINT32,
-        bitlen = 32,
-        is_little_endian = true,
-        description = "timestamp microseconds",
-    })
-    fields[path .. "._payload__size"] = AlignedProtoField:new({
-        name = "Size(Payload)",
-        abbr = path .. "._payload__size",
-        ftype = ftypes.UINT32,
-        bitlen = 32,
-        is_little_endian = true,
-        description = "number of octets of packet saved in file",
-    })
-    fields[path .. ".orig_len"] = AlignedProtoField:new({
-        name = "orig_len",
-        abbr = path .. ".orig_len",
-        ftype = ftypes.UINT32,
-        bitlen = 32,
-        is_little_endian = true,
-        description = "actual length of packet",
-    })
-    fields[path .. "._payload_"] = AlignedProtoField:new({
-        name = "Payload",
-        abbr = path .. "._payload_",
-        ftype = ftypes.BYTES,
-        bitlen = nil,
-        is_little_endian = true,
-        description = "packet octets",
+        description = "The number of items in `records`",
     })
 end
 -- Sequence: PcapFile (2 fields, 0 children, 0 constraints)
-function PcapFile_dissect(buffer, pinfo, tree, fields, path)
+function PcapFile_dissect(buffer, pinfo, tree, fields, experts, path, field_values)
     local i = 0
-    local field_values = {}
-    -- Typedef: header
-    local field_len = enforce_len_limit(24, buffer(i):len(), tree)
-    local subtree = tree:add(buffer(i, field_len), "header")
-    local dissected_len = PcapHeader_dissect(buffer(i, field_len), pinfo, subtree, fields, path)
-    subtree:set_len(dissected_len)
-    i = i + dissected_len
-    -- TypedefArray: records
-    local initial_i = i
-    while i < buffer:len() do    -- TypedefArray: records
-        local field_len = enforce_len_limit(sum_or_nil(16, field_values[path .. "._payload__size"]), buffer(i):len(), tree)
-        local subtree = tree:add(buffer(i, field_len), "records")
-        local dissected_len = PcapRecord_dissect(buffer(i, field_len), pinfo, subtree, fields, path)
+    field_values = field_values or {}
+    local current_field = nil
+    local ok, err = pcall(function()
+        current_field = "header"
+        -- Typedef: header
+        local field_len = enforce_len_limit(24, buffer(i), tree, experts)
+        local subtree = tree:add(buffer(i, field_len), "header")
+        local dissected_len = PcapHeader_dissect(field_buffer(buffer, i, field_len, 24), pinfo, subtree, fields, experts, path .. ".header")
         subtree:set_len(dissected_len)
-        i = i + dissected_len
+        i = check_consumed_len(tree, experts, buffer, "Size(" .. "header" .. ")", 24, i, i + dissected_len)
+        current_field = "records"
+        local array_tree = tree:add(buffer(i, 0), "records")
+        -- TypedefArray: records
+        local initial_i = i
+        local item_count = 0
+        while i < buffer:len() do
+            if item_count >= ARRAY_MAX_COUNT then
+                tree:add_proto_expert_info(experts.array_stopped, "Error: Stopped dissecting `records` after " .. ARRAY_MAX_COUNT .. " items")
+                break
+            end
+            local index = item_count
+            local item_start = i
+            -- TypedefArray: records
+            local field_len = enforce_len_limit(sum_or_nil(16, field_values[path .. "._payload__size"]), buffer(i), tree, experts)
+            local subtree = array_tree:add(buffer(i, field_len), "records[" .. index .. "]")
+            local dissected_len = PcapRecord_dissect(field_buffer(buffer, i, field_len, sum_or_nil(16, field_values[path .. "._payload__size"])), pinfo, subtree, fields, experts, path .. ".records")
+            subtree:set_len(dissected_len)
+            i = check_consumed_len(array_tree, experts, buffer, "Size(" .. "records[" .. index .. "]" .. ")", sum_or_nil(16, field_values[path .. "._payload__size"]), i, i + dissected_len)
+            item_count = item_count + 1
+            if i == item_start then
+                tree:add_proto_expert_info(experts.array_stopped, "Error: Stopped dissecting `records` at an item of length 0")
+                break
+            end
+        end
+        local array_len = i - initial_i
+        array_tree:set_len(array_len)
+        array_tree:append_text(" (" .. item_count .. (item_count == 1 and " item, " or " items, ") .. string.format("%g", array_len) .. " bytes)")
+        array_tree:add(fields[path .. ".records_item_count"].field, item_count):set_generated()
+    end)
+    if not ok then
+        i = report_dissect_error(tree, experts, buffer, i, "PcapFile", current_field, err)
     end
     return i
 end
@@ -478,21 +592,39 @@ function PcapFile_match_constraints(field_values, path)
 end
 -- Protocol definition for "PcapFile"
 PcapFile_protocol = Proto("PcapFile",  "PcapFile")
+PcapFile_protocol_experts = {
+    length_overrun = ProtoExpert.new("pcapfile.expert.length_overrun", "Field extends past the end of the packet", expert.group.MALFORMED, expert.severity.WARN),
+    size_mismatch = ProtoExpert.new("pcapfile.expert.size_mismatch", "Field length differs from its declared size", expert.group.MALFORMED, expert.severity.WARN),
+    fixed_mismatch = ProtoExpert.new("pcapfile.expert.fixed_mismatch", "Field does not have its expected value", expert.group.MALFORMED, expert.severity.WARN),
+    unknown_enum = ProtoExpert.new("pcapfile.expert.unknown_enum", "Unknown enum value", expert.group.MALFORMED, expert.severity.WARN),
+    array_count_short = ProtoExpert.new("pcapfile.expert.array_count_short", "Array has fewer items than its count", expert.group.MALFORMED, expert.severity.WARN),
+    array_stopped = ProtoExpert.new("pcapfile.expert.array_stopped", "Stopped dissecting an array", expert.group.MALFORMED, expert.severity.WARN),
+    padding_short = ProtoExpert.new("pcapfile.expert.padding_short", "Padded array is shorter than its padding", expert.group.MALFORMED, expert.severity.WARN),
+    trailing_bytes = ProtoExpert.new("pcapfile.expert.trailing_bytes", "Undissected bytes after the packet", expert.group.MALFORMED, expert.severity.WARN),
+    no_child_matched = ProtoExpert.new("pcapfile.expert.no_child_matched", "Body matched no child packet", expert.group.UNDECODED, expert.severity.NOTE),
+    dissect_error = ProtoExpert.new("pcapfile.expert.dissect_error", "Malformed packet", expert.group.MALFORMED, expert.severity.ERROR),
+}
 PcapFile_protocol_fields_table = {}
 function PcapFile_protocol.dissector(buffer, pinfo, tree)
     pinfo.cols.protocol = "PcapFile"
     local subtree = tree:add(PcapFile_protocol, buffer(), "PcapFile")
-    local i = PcapFile_dissect(buffer, pinfo, subtree, PcapFile_protocol_fields_table, "PcapFile")
+    local ok, i = pcall(PcapFile_dissect, buffer, pinfo, subtree, PcapFile_protocol_fields_table, PcapFile_protocol_experts, "pcapfile")
+    if not ok then
+        i = report_truncated(subtree, buffer, "PcapFile", i)
+    end
     if buffer(i):len() > 0 then
         local remaining_bytes = buffer:len() - i
         if math.floor(remaining_bytes) == remaining_bytes then
-            subtree:add_expert_info(PI_MALFORMED, PI_WARN, "Error: " .. remaining_bytes .. " undissected bytes remaining")
+            subtree:add_proto_expert_info(PcapFile_protocol_experts.trailing_bytes, "Error: " .. remaining_bytes .. " undissected bytes remaining")
         else
-            subtree:add_expert_info(PI_MALFORMED, PI_WARN, "Error: " .. (remaining_bytes * 8) .. " undissected bits remaining")
+            subtree:add_proto_expert_info(PcapFile_protocol_experts.trailing_bytes, "Error: " .. (remaining_bytes * 8) .. " undissected bits remaining")
         end
     end
 end
-PcapFile_protocol_fields(PcapFile_protocol_fields_table, "PcapFile")
+PcapFile_protocol_fields(PcapFile_protocol_fields_table, "pcapfile")
 for name,field in pairs(PcapFile_protocol_fields_table) do
     PcapFile_protocol.fields[name] = field.field
 end
+for name,expert in pairs(PcapFile_protocol_experts) do
+    PcapFile_protocol.experts[name] = expert
+end
@@ -0,0 +1,171 @@
+//! Generating dissectors from the `build.rs` of a crate, so that they are regenerated whenever
+//! its PDL files change.
+//!
+//! ```no_run
+//! // In `fn main` of build.rs
+//! pdl_dissector::build::Build::new()
+//!     .dissector("src/packets.pdl", ["Message"], "packets.lua")
+//!     .generate();
+//! ```
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use codespan_reporting::term::termcolor::NoColor;
+use pdl_compiler::ast::SourceDatabase;
+
+use crate::{diagnostics::Diagnostics, Generator};
+
+/// The dissectors to generate from a build script.
+#[derive(Debug, Default)]
+pub struct Build {
+    out_dir: Option<PathBuf>,
+    dissectors: Vec<(Generator, PathBuf)>,
+}
+
+impl Build {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the directory that relative output paths are written to. Defaults to `OUT_DIR`.
+    pub fn out_dir(mut self, out_dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = Some(out_dir.into());
+        self
+    }
+
+    /// Generates the protocols of `targets` in `pdl_file` into `output`.
+    pub fn dissector(
+        self,
+        pdl_file: impl Into<PathBuf>,
+        targets: impl IntoIterator<Item = impl Into<String>>,
+        output: impl Into<PathBuf>,
+    ) -> Self {
+        self.generator(Generator::from_file(pdl_file).targets(targets), output)
+    }
+
+    /// Generates the dissector of `generator`, with all of its options, into `output`.
+    pub fn generator(mut self, generator: Generator, output: impl Into<PathBuf>) -> Self {
+        self.dissectors.push((generator, output.into()));
+        self
+    }
+
+    /// Generates the dissectors, and returns the paths of the written Lua files. Panics with the
+    /// rendered diagnostics on errors, which fails the build.
+    pub fn generate(self) -> Vec<PathBuf> {
+        self.try_generate(&mut std::io::stdout())
+            .unwrap_or_else(|errors| panic!("Failed to generate dissectors:\n{errors}"))
+    }
+
+    /// Generates the dissectors, writing the instructions for cargo into `cargo_output`, and
+    /// returns the paths of the written Lua files, or the rendered diagnostics on errors.
+    /// Warnings are passed to cargo to be shown in the build output.
+    pub fn try_generate(self, cargo_output: &mut impl Write) -> Result<Vec<PathBuf>, String> {
+        let out_dir = match self.out_dir {
+            Some(out_dir) => out_dir,
+            None => std::env::var_os("OUT_DIR")
+                .ok_or("OUT_DIR is not set, which cargo sets for build scripts")?
+                .into(),
+        };
+        let mut sources = SourceDatabase::new();
+        let mut outputs = vec![];
+        for (generator, output) in &self.dissectors {
+            let write_error = |err: std::io::Error| err.to_string();
+            for input in generator.input_files() {
+                writeln!(cargo_output, "cargo:rerun-if-changed={}", input.display())
+                    .map_err(write_error)?;
+            }
+            let mut dissector = vec![];
+            let warnings = generator
+                .generate(&mut sources, &mut dissector)
+                .map_err(|errors| render(&errors, &sources))?;
+            for line in render(&warnings, &sources).lines() {
+                writeln!(cargo_output, "cargo:warning={line}").map_err(write_error)?;
+            }
+            let output = out_dir.join(output);
+            write_if_changed(&output, &dissector)
+                .map_err(|err| format!("Unable to write {}: {err}", output.display()))?;
+            outputs.push(output);
+        }
+        Ok(outputs)
+    }
+}
+
+/// Renders `diagnostics` as plain text, with the code they refer to.
+fn render(diagnostics: &Diagnostics, sources: &SourceDatabase) -> String {
+    let mut writer = NoColor::new(vec![]);
+    match diagnostics.emit(sources, &mut writer) {
+        Ok(()) => String::from_utf8_lossy(&writer.into_inner()).into_owned(),
+        Err(_) => diagnostics.to_string(),
+    }
+}
+
+/// Writes `contents` to `path` unless it already has them, so that its modification time only
+/// changes when the dissector does.
+fn write_if_changed(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if std::fs::read(path).is_ok_and(|existing| existing == contents) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Build;
+    use crate::{fakes::wireshark_lua, Generator};
+
+    #[test]
+    fn test_build_script() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pdl_file = dir.path().join("packets.pdl");
+        std::fs::write(
+            &pdl_file,
+            "little_endian_packets\npacket Message {\n  value: 16,\n}\n",
+        )?;
+        let out_dir = dir.path().join("out");
+        let mut cargo_output = vec![];
+        let outputs = Build::new()
+            .out_dir(&out_dir)
+            .dissector(&pdl_file, ["Message"], "wireshark/packets.lua")
+            .generator(
+                Generator::from_file(&pdl_file)
+                    .target("Message")
+                    .protocol_name("MSG"),
+                "msg.lua",
+            )
+            .try_generate(&mut cargo_output)
+            .map_err(anyhow::Error::msg)?;
+        assert_eq!(
+            outputs,
+            vec![
+                out_dir.join("wireshark/packets.lua"),
+                out_dir.join("msg.lua")
+            ]
+        );
+        let cargo_output = String::from_utf8(cargo_output)?;
+        assert!(cargo_output.contains(&format!("cargo:rerun-if-changed={}", pdl_file.display())));
+        wireshark_lua()?
+            .load(std::fs::read_to_string(&outputs[0])?)
+            .exec()?;
+        assert!(std::fs::read_to_string(&outputs[1])?.contains(r#"Proto("MSG",  "MSG")"#));
+
+        // Errors are rendered with the code they refer to.
+        std::fs::write(
+            &pdl_file,
+            "little_endian_packets\npacket Message {\n  value: 16, // @base=binary\n}\n",
+        )?;
+        let error = Build::new()
+            .out_dir(&out_dir)
+            .dissector(&pdl_file, ["Message"], "packets.lua")
+            .try_generate(&mut vec![])
+            .unwrap_err();
+        assert!(error.contains("error: Unknown base `binary`"), "{error}");
+        assert!(error.contains("value: 16, // @base=binary"), "{error}");
+        Ok(())
+    }
+}
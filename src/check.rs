@@ -0,0 +1,21 @@
+//! Checking that a committed dissector is up to date with the PDL files and options it is generated
+//! from.
+
+use std::path::Path;
+
+use similar::TextDiff;
+
+/// Returns a unified diff from `existing`, the contents of the file at `path`, to the `generated`
+/// dissector, or `None` if they are the same.
+pub fn diff(path: &Path, existing: &str, generated: &str) -> Option<String> {
+    if existing == generated {
+        return None;
+    }
+    Some(
+        TextDiff::from_lines(existing, generated)
+            .unified_diff()
+            .context_radius(3)
+            .header(&path.display().to_string(), "generated")
+            .to_string(),
+    )
+}
@@ -3,6 +3,8 @@ use pdl_compiler::{
     ast::{Comment, SourceRange},
 };
 
+use crate::utils::lua_string;
+
 pub fn find_comments_on_same_line<'a>(file: &'a File, source: &SourceRange) -> Option<&'a Comment> {
     file.comments.iter().find(|comment| {
         comment.loc.file == source.file && comment.loc.start.line == source.end.line
@@ -17,12 +19,88 @@ pub fn unwrap_comment(s: &str) -> &str {
         .unwrap_or_default()
 }
 
+/// Returns the description of the field at `loc`, which is the comment on the same line with its
+/// annotations removed.
+pub fn field_description(file: &File, loc: &SourceRange) -> Option<String> {
+    find_comments_on_same_line(file, loc)
+        .map(|comment| parse_annotations(unwrap_comment(&comment.text)).0)
+        .filter(|description| !description.is_empty())
+}
+
+/// An annotation in a comment, written as `@key` or `@key=value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentAnnotation {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+/// Splits the text of a comment into its description and the annotations in it.
+pub fn parse_annotations(text: &str) -> (String, Vec<CommentAnnotation>) {
+    let mut description = vec![];
+    let mut annotations = vec![];
+    for word in text.split_whitespace() {
+        match word.strip_prefix('@') {
+            Some(annotation) if !annotation.is_empty() => {
+                let (key, value) = match annotation.split_once('=') {
+                    Some((key, value)) => (key, Some(value.to_string())),
+                    None => (annotation, None),
+                };
+                annotations.push(CommentAnnotation {
+                    key: key.to_string(),
+                    value,
+                });
+            }
+            _ => description.push(word),
+        }
+    }
+    (description.join(" "), annotations)
+}
+
 pub trait ToLuaExpr {
     fn to_lua_expr(&self) -> String;
 }
 
 impl ToLuaExpr for Option<&str> {
     fn to_lua_expr(&self) -> String {
-        self.map(|t| format!("\"{t}\"")).unwrap_or("nil".into())
+        self.map(lua_string).unwrap_or("nil".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_annotations, CommentAnnotation};
+
+    #[test]
+    fn test_parse_annotations() {
+        assert_eq!(
+            (
+                String::from("Source address"),
+                vec![CommentAnnotation {
+                    key: "display".into(),
+                    value: Some("ipv4".into())
+                }]
+            ),
+            parse_annotations("Source address @display=ipv4")
+        );
+        assert_eq!(
+            (
+                String::new(),
+                vec![
+                    CommentAnnotation {
+                        key: "base".into(),
+                        value: Some("hex".into())
+                    },
+                    CommentAnnotation {
+                        key: "unit".into(),
+                        value: Some("ms".into())
+                    }
+                ]
+            ),
+            parse_annotations("@base=hex @unit=ms")
+        );
+        assert_eq!(
+            (String::from("Email me @ home"), vec![]),
+            parse_annotations("Email me @ home")
+        );
     }
 }
@@ -0,0 +1,311 @@
+//! Project files, named `pdl-dissector.toml`, which declare the dissectors to generate in one run,
+//! so that the options of each dissector can be versioned along with its PDL file.
+
+use std::path::{Path, PathBuf};
+
+use clap::{parser::ValueSource, ArgMatches};
+use codespan_reporting::diagnostic::Diagnostic;
+use serde::Deserialize;
+
+use crate::{diagnostics::Diagnostics, AbbrStyle, Args, Binding, DissectorOptions, Framing};
+
+/// The name of the project file that is used when no PDL file is given on the command line.
+pub const PROJECT_FILE_NAME: &str = "pdl-dissector.toml";
+
+/// The contents of a project file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Project {
+    /// The dissectors to generate, each declared in a `[[dissector]]` table.
+    #[serde(default, rename = "dissector")]
+    pub dissectors: Vec<DissectorConfig>,
+}
+
+/// A dissector generated from a single PDL file. The keys have the same meaning as the command
+/// line options of the same name.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct DissectorConfig {
+    pub pdl_file: PathBuf,
+    #[serde(rename = "targets")]
+    pub target_packets: Vec<String>,
+    /// The Lua file to write the dissector to. Without it, the dissector is written to stdout.
+    pub output: Option<PathBuf>,
+    pub protocol_name: Option<String>,
+    pub protocol_full_name: Option<String>,
+    pub filter_prefix: Option<String>,
+    #[serde(default)]
+    pub framing: Framing,
+    #[serde(default)]
+    pub tcp_ports: Vec<u16>,
+    #[serde(default)]
+    pub udp_ports: Vec<u16>,
+    pub info: Option<String>,
+    #[serde(default)]
+    pub module: bool,
+    #[serde(default)]
+    pub abbr_style: AbbrStyle,
+    #[serde(default)]
+    pub boolean_fields: Vec<String>,
+    #[serde(default)]
+    pub expand_byte_arrays: bool,
+    /// Bindings written as `<declaration>.<field>=<file>:<packet>`, like `--bind`.
+    #[serde(default)]
+    pub bind: Vec<String>,
+}
+
+impl Project {
+    /// Reads the project file at `path`.
+    pub fn load(path: &Path) -> Result<Self, Diagnostics> {
+        let text = std::fs::read_to_string(path).map_err(|err| {
+            Diagnostic::error()
+                .with_message(format!(
+                    "Unable to read the project file {}: {err}",
+                    path.display()
+                ))
+                .with_notes(vec![
+                    "Give a PDL file and target packets, or a project file with `--config`".into(),
+                ])
+        })?;
+        let project: Project = toml::from_str(&text).map_err(|err| {
+            Diagnostic::error().with_message(format!(
+                "Invalid project file {}: {}",
+                path.display(),
+                err.to_string().trim_end()
+            ))
+        })?;
+        if project.dissectors.is_empty() {
+            Err(Diagnostic::error().with_message(format!(
+                "The project file {} doesn't declare any `[[dissector]]`",
+                path.display()
+            )))?
+        }
+        if project
+            .dissectors
+            .iter()
+            .filter(|dissector| dissector.output.is_none())
+            .count()
+            > 1
+        {
+            Err(Diagnostic::error().with_message(format!(
+                "Only one dissector in the project file {} can be written to stdout, the others need an `output`",
+                path.display()
+            )))?
+        }
+        Ok(project)
+    }
+
+    /// Returns the arguments to generate each dissector with. Relative paths in the project file
+    /// are resolved against `base_dir`. Options that were given on the command line, according to
+    /// `matches`, are taken from `cli` instead of the project file.
+    pub fn to_args(
+        &self,
+        base_dir: &Path,
+        cli: &Args,
+        matches: &ArgMatches,
+    ) -> Result<Vec<Args>, Diagnostics> {
+        let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        if given("output") {
+            Err(Diagnostic::error().with_message(
+                "`--output` cannot be used with a project file, set the `output` of each dissector instead",
+            ))?
+        }
+        let mut all_args = vec![];
+        for dissector in &self.dissectors {
+            let bindings = dissector
+                .bind
+                .iter()
+                .map(|binding| {
+                    let mut binding = binding.parse::<Binding>().map_err(|message| {
+                        Diagnostic::error().with_message(format!("Invalid `bind`: {message}"))
+                    })?;
+                    binding.pdl_file = base_dir.join(&binding.pdl_file);
+                    Ok(binding)
+                })
+                .collect::<Result<Vec<_>, Diagnostics>>()?;
+            let mut args = Args {
+                pdl_file: Some(base_dir.join(&dissector.pdl_file)),
+                target_packets: dissector.target_packets.clone(),
+                output: dissector
+                    .output
+                    .as_ref()
+                    .map(|output| base_dir.join(output)),
+                protocol_name: dissector.protocol_name.clone(),
+                protocol_full_name: dissector.protocol_full_name.clone(),
+                filter_prefix: dissector.filter_prefix.clone(),
+                framing: dissector.framing,
+                tcp_ports: dissector.tcp_ports.clone(),
+                udp_ports: dissector.udp_ports.clone(),
+                info: dissector.info.clone(),
+                module: dissector.module,
+                check: None,
+                config: None,
+                options: DissectorOptions {
+                    abbr_style: dissector.abbr_style,
+                    boolean_fields: dissector.boolean_fields.clone(),
+                    expand_byte_arrays: dissector.expand_byte_arrays,
+                    bindings,
+                },
+            };
+            if given("protocol_name") {
+                args.protocol_name.clone_from(&cli.protocol_name);
+            }
+            if given("protocol_full_name") {
+                args.protocol_full_name.clone_from(&cli.protocol_full_name);
+            }
+            if given("filter_prefix") {
+                args.filter_prefix.clone_from(&cli.filter_prefix);
+            }
+            if given("framing") {
+                args.framing = cli.framing;
+            }
+            if given("tcp_ports") {
+                args.tcp_ports.clone_from(&cli.tcp_ports);
+            }
+            if given("udp_ports") {
+                args.udp_ports.clone_from(&cli.udp_ports);
+            }
+            if given("info") {
+                args.info.clone_from(&cli.info);
+            }
+            if given("module") {
+                args.module = cli.module;
+            }
+            if given("abbr_style") {
+                args.options.abbr_style = cli.options.abbr_style;
+            }
+            if given("boolean_fields") {
+                args.options
+                    .boolean_fields
+                    .clone_from(&cli.options.boolean_fields);
+            }
+            if given("expand_byte_arrays") {
+                args.options.expand_byte_arrays = cli.options.expand_byte_arrays;
+            }
+            if given("bindings") {
+                args.options.bindings.clone_from(&cli.options.bindings);
+            }
+            all_args.push(args);
+        }
+        Ok(all_args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufWriter;
+
+    use clap::{CommandFactory as _, FromArgMatches as _};
+    use indoc::indoc;
+    use pdl_compiler::ast::SourceDatabase;
+
+    use crate::{fakes::wireshark_lua, ir, run_cli, run_project, Args, Cli, Framing};
+
+    #[test]
+    fn test_project_file() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("link.pdl"),
+            "little_endian_packets\npacket Frame {\n  _size_(_payload_): 8,\n  _payload_,\n}\n",
+        )?;
+        std::fs::write(
+            dir.path().join("app.pdl"),
+            "big_endian_packets\npacket Message {\n  value: 16,\n}\n",
+        )?;
+        let project_file = dir.path().join("pdl-dissector.toml");
+        std::fs::write(
+            &project_file,
+            indoc! {r#"
+                [[dissector]]
+                pdl-file = "link.pdl"
+                targets = ["Frame"]
+                output = "link.lua"
+                protocol-name = "LINK"
+                framing = "tcp"
+                bind = ["Frame._payload_=app.pdl:Message"]
+
+                [[dissector]]
+                pdl-file = "app.pdl"
+                targets = ["Message"]
+                tcp-ports = [8000, 8001]
+                udp-ports = [8000]
+                info = "Value {value}"
+            "#},
+        )?;
+        // Options on the command line override the project file.
+        let matches = Args::command().try_get_matches_from([
+            "pdl-dissector".as_ref(),
+            "--config".as_ref(),
+            project_file.as_os_str(),
+            "--framing".as_ref(),
+            "multiple".as_ref(),
+        ])?;
+        let cli = Args::from_arg_matches(&matches)?;
+        assert_eq!(cli.project_file(), Some(project_file.clone()));
+        let mut writer = BufWriter::new(Vec::new());
+        run_project(
+            &project_file,
+            &cli,
+            &matches,
+            &mut SourceDatabase::new(),
+            &mut writer,
+        )?;
+        let link = std::fs::read_to_string(dir.path().join("link.lua"))?;
+        assert!(link.contains(r#"Frame_protocol = Proto("LINK",  "LINK")"#));
+        assert!(link.contains("local pdu_count = 0"));
+        assert!(link.contains(
+            r#"    Message_protocol = Proto("Frame.payload",  "Message (Frame.payload)")"#
+        ));
+        let app = String::from_utf8(writer.into_inner()?)?;
+        assert!(app.contains(r#"Message_protocol = Proto("Message",  "Message")"#));
+        assert!(app.contains(indoc! {r#"
+            DissectorTable.get("tcp.port"):add(8000, Message_protocol)
+            DissectorTable.get("tcp.port"):add(8001, Message_protocol)
+            DissectorTable.get("udp.port"):add(8000, Message_protocol)
+        "#}));
+        assert!(app.contains(r#"pinfo.cols.info:append(", " .. "Value " .. info_value(field_values["message.value"]))"#));
+        wireshark_lua()?.load(link).exec()?;
+        wireshark_lua()?.load(app).exec()?;
+
+        // `ir` prints the file of each dissector.
+        let matches = Cli::command().try_get_matches_from([
+            "pdl-dissector".as_ref(),
+            "ir".as_ref(),
+            "--config".as_ref(),
+            project_file.as_os_str(),
+        ])?;
+        let mut output = vec![];
+        run_cli(
+            Cli::from_arg_matches(&matches)?,
+            &matches,
+            &mut SourceDatabase::new(),
+            &mut output,
+        )?;
+        let files: Vec<ir::File> = serde_json::from_slice(&output)?;
+        let protocols = files
+            .iter()
+            .flat_map(|file| &file.protocols)
+            .map(|protocol| (protocol.short_name.as_str(), protocol.framing))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            protocols,
+            [("LINK", Framing::Tcp), ("Message", Framing::Single)]
+        );
+
+        std::fs::write(
+            &project_file,
+            "[[dissector]]\npdl-file = \"link.pdl\"\ntarget = [\"Frame\"]\n",
+        )?;
+        let error = run_project(
+            &project_file,
+            &cli,
+            &matches,
+            &mut SourceDatabase::new(),
+            &mut BufWriter::new(Vec::new()),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("unknown field `target`"), "{error}");
+        Ok(())
+    }
+}
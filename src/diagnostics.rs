@@ -19,6 +19,15 @@ impl Diagnostics {
         }
         Ok(())
     }
+
+    /// The errors or warnings, e.g. to inspect their severity, message and labels.
+    pub fn as_slice(&self) -> &[Diagnostic<FileId>] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<Diagnostic<FileId>> {
+        self.0
+    }
 }
 
 impl std::fmt::Display for Diagnostics {
@@ -41,6 +50,12 @@ impl From<codespan_reporting::diagnostic::Diagnostic<FileId>> for Diagnostics {
     }
 }
 
+impl From<Vec<codespan_reporting::diagnostic::Diagnostic<FileId>>> for Diagnostics {
+    fn from(diagnostics: Vec<codespan_reporting::diagnostic::Diagnostic<FileId>>) -> Self {
+        Self(diagnostics)
+    }
+}
+
 impl From<std::io::Error> for Diagnostics {
     fn from(value: std::io::Error) -> Self {
         Self(vec![Diagnostic::error().with_message(value.to_string())])
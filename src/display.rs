@@ -0,0 +1,331 @@
+//! How the values of scalar fields and byte arrays are displayed. Besides the defaults and the
+//! [`DissectorOptions`], this can be chosen with annotations in the comment on the same line as
+//! the field, e.g. `src: 32, // Source address @display=ipv4`.
+
+use codespan_reporting::diagnostic::Diagnostic;
+use pdl_compiler::{
+    analyzer::ast::{Decl, Field, File},
+    ast::{FieldDesc, FileId},
+};
+
+use crate::{
+    comments::{find_comments_on_same_line, parse_annotations, unwrap_comment},
+    handoff::HANDOFF_KEYS,
+    len_info::BitLen,
+    DissectorOptions,
+};
+
+/// How the value of a scalar field or byte array is displayed.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum FieldDisplay {
+    /// Displayed as an unsigned integer, or as bytes for fields wider than 64 bits.
+    Integer {
+        base: Option<IntegerBase>,
+        /// Text appended to the value, e.g. `ms`.
+        unit: Option<String>,
+    },
+    /// Displayed as a boolean, with the given strings for non-zero and zero values.
+    Boolean {
+        true_string: String,
+        false_string: String,
+    },
+    /// Displayed as a Wireshark field type other than integers, e.g. an IPv4 address.
+    Typed(DisplayType),
+}
+
+impl Default for FieldDisplay {
+    fn default() -> Self {
+        Self::Integer {
+            base: None,
+            unit: None,
+        }
+    }
+}
+
+impl FieldDisplay {
+    fn boolean() -> Self {
+        Self::Boolean {
+            true_string: "Set".into(),
+            false_string: "Not set".into(),
+        }
+    }
+
+    fn flag(optional_field_id: &str, set_value: usize) -> Self {
+        let presence = |present: bool| if present { "present" } else { "absent" };
+        Self::Boolean {
+            true_string: format!("Set ({optional_field_id} {})", presence(set_value == 1)),
+            false_string: format!("Not set ({optional_field_id} {})", presence(set_value == 0)),
+        }
+    }
+}
+
+/// The base of an integer field, set with `@base=<base>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum IntegerBase {
+    Dec,
+    Hex,
+    Oct,
+    DecHex,
+    HexDec,
+}
+
+impl IntegerBase {
+    const NAMES: &'static [(&'static str, IntegerBase)] = &[
+        ("dec", IntegerBase::Dec),
+        ("hex", IntegerBase::Hex),
+        ("oct", IntegerBase::Oct),
+        ("dec_hex", IntegerBase::DecHex),
+        ("hex_dec", IntegerBase::HexDec),
+    ];
+
+    pub fn to_lua_expr(self) -> &'static str {
+        match self {
+            IntegerBase::Dec => "base.DEC",
+            IntegerBase::Hex => "base.HEX",
+            IntegerBase::Oct => "base.OCT",
+            IntegerBase::DecHex => "base.DEC_HEX",
+            IntegerBase::HexDec => "base.HEX_DEC",
+        }
+    }
+}
+
+/// A Wireshark field type, set with `@display=<type>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum DisplayType {
+    Ipv4,
+    Ipv6,
+    Ether,
+    Guid,
+    AbsoluteTime,
+    String,
+    Stringz,
+    Bytes,
+}
+
+impl DisplayType {
+    const NAMES: &'static [(&'static str, DisplayType)] = &[
+        ("ipv4", DisplayType::Ipv4),
+        ("ipv6", DisplayType::Ipv6),
+        ("ether", DisplayType::Ether),
+        ("guid", DisplayType::Guid),
+        ("absolute_time", DisplayType::AbsoluteTime),
+        ("string", DisplayType::String),
+        ("stringz", DisplayType::Stringz),
+        ("bytes", DisplayType::Bytes),
+    ];
+
+    pub fn to_lua_expr(self) -> &'static str {
+        match self {
+            DisplayType::Ipv4 => "ftypes.IPv4",
+            DisplayType::Ipv6 => "ftypes.IPv6",
+            DisplayType::Ether => "ftypes.ETHER",
+            DisplayType::Guid => "ftypes.GUID",
+            DisplayType::AbsoluteTime => "ftypes.ABSOLUTE_TIME",
+            DisplayType::String => "ftypes.STRING",
+            DisplayType::Stringz => "ftypes.STRINGZ",
+            DisplayType::Bytes => "ftypes.BYTES",
+        }
+    }
+
+    /// Whether fields of this type are always in network byte order, whatever the endianness of
+    /// the PDL file, like addresses.
+    pub fn is_network_order(self) -> bool {
+        matches!(
+            self,
+            DisplayType::Ipv4 | DisplayType::Ipv6 | DisplayType::Ether
+        )
+    }
+
+    /// Whether a field of this type can be `len` bits long, where `None` is a variable length.
+    fn accepts_len(self, len: Option<usize>) -> bool {
+        match self {
+            DisplayType::Ipv4 => len == Some(32),
+            DisplayType::Ipv6 | DisplayType::Guid => len == Some(128),
+            DisplayType::Ether => len == Some(48),
+            DisplayType::AbsoluteTime => matches!(len, Some(32 | 64)),
+            DisplayType::String | DisplayType::Stringz | DisplayType::Bytes => true,
+        }
+    }
+
+    fn expected_len(self) -> &'static str {
+        match self {
+            DisplayType::Ipv4 => "4 bytes",
+            DisplayType::Ipv6 | DisplayType::Guid => "16 bytes",
+            DisplayType::Ether => "6 bytes",
+            DisplayType::AbsoluteTime => "4 or 8 bytes",
+            DisplayType::String | DisplayType::Stringz | DisplayType::Bytes => {
+                "any number of bytes"
+            }
+        }
+    }
+}
+
+fn parse_name<T: Copy>(names: &[(&str, T)], name: &str) -> Option<T> {
+    names.iter().find(|(n, _)| *n == name).map(|(_, v)| *v)
+}
+
+fn name_list<T>(names: &[(&str, T)]) -> String {
+    names
+        .iter()
+        .map(|(n, _)| format!("`{n}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns how `field` in `decl` is displayed, or an error if the annotations of the field are
+/// invalid or cannot be used with this field.
+pub fn field_display(
+    field: &Field,
+    decl: &Decl,
+    bit_offset: BitLen,
+    options: &DissectorOptions,
+    file: &File,
+) -> Result<FieldDisplay, Diagnostic<FileId>> {
+    let comment = find_comments_on_same_line(file, &field.loc);
+    let (handoff_annotations, annotations): (Vec<_>, Vec<_>) = comment
+        .map(|comment| parse_annotations(unwrap_comment(&comment.text)).1)
+        .unwrap_or_default()
+        .into_iter()
+        .partition(|annotation| HANDOFF_KEYS.contains(&annotation.key.as_str()));
+    let error = |message: String| {
+        let mut diagnostic = Diagnostic::error().with_message(message);
+        if let Some(comment) = comment {
+            diagnostic = diagnostic.with_labels(vec![
+                comment.loc.primary(),
+                field.loc.secondary().with_message("in this field"),
+            ]);
+        }
+        diagnostic
+    };
+
+    // `len` is the length of the field in bits, or `None` for byte arrays of variable length.
+    let (default, len, is_byte_array) = match &field.desc {
+        FieldDesc::Flag {
+            optional_field_id,
+            set_value,
+            ..
+        } => {
+            if !annotations.is_empty() {
+                Err(error(
+                    "Flags of optional fields are always displayed as booleans".into(),
+                ))?
+            }
+            return Ok(FieldDisplay::flag(optional_field_id, *set_value));
+        }
+        FieldDesc::Scalar { id, width } => {
+            let is_boolean =
+                *width == 1 || options.is_boolean_field(decl.id().unwrap_or_default(), id);
+            let default = if is_boolean {
+                FieldDisplay::boolean()
+            } else {
+                FieldDisplay::default()
+            };
+            (default, Some(*width), false)
+        }
+        FieldDesc::Array {
+            width: Some(8),
+            type_id: None,
+            size,
+            ..
+        } => {
+            // Byte arrays are displayed as a single item, unless each byte is shown separately.
+            // Byte arrays handed to another dissector are always a single item.
+            let default = if options.expand_byte_arrays && handoff_annotations.is_empty() {
+                FieldDisplay::default()
+            } else {
+                FieldDisplay::Typed(DisplayType::Bytes)
+            };
+            (default, size.map(|size| size * 8), true)
+        }
+        _ => {
+            if !annotations.is_empty() {
+                Err(error(
+                    "Display annotations can only be used on scalar fields and byte arrays".into(),
+                ))?
+            }
+            return Ok(FieldDisplay::default());
+        }
+    };
+
+    let mut display_type = None;
+    let mut boolean = false;
+    let mut base = None;
+    let mut unit = None;
+    for annotation in &annotations {
+        let key = annotation.key.as_str();
+        let Some(value) = annotation.value.as_deref() else {
+            Err(error(format!(
+                "Annotation `@{key}` requires a value, e.g. `@{key}=<value>`"
+            )))?
+        };
+        match key {
+            "display" if value == "boolean" => boolean = true,
+            "display" => {
+                let parsed = parse_name(DisplayType::NAMES, value).ok_or_else(|| {
+                    error(format!(
+                        "Unknown display type `{value}`, expected `boolean`, {}",
+                        name_list(DisplayType::NAMES)
+                    ))
+                })?;
+                display_type = Some((value, parsed));
+            }
+            "base" => {
+                base = Some(parse_name(IntegerBase::NAMES, value).ok_or_else(|| {
+                    error(format!(
+                        "Unknown base `{value}`, expected one of {}",
+                        name_list(IntegerBase::NAMES)
+                    ))
+                })?)
+            }
+            "unit" => unit = Some(value.to_string()),
+            _ => Err(error(format!(
+                "Unknown annotation `@{key}`, expected `@display`, `@base`, `@unit`, `@dissector` or `@dissector_table`"
+            )))?,
+        }
+    }
+
+    if (base.is_some() || unit.is_some()) && (boolean || display_type.is_some() || is_byte_array) {
+        Err(error(
+            "`@base` and `@unit` can only be used on fields displayed as integers".into(),
+        ))?
+    }
+    if boolean {
+        if is_byte_array || len.map_or(true, |len| len > 64) {
+            Err(error(
+                "Only scalar fields of up to 64 bits can be displayed as booleans".into(),
+            ))?
+        }
+        return Ok(FieldDisplay::boolean());
+    }
+    if let Some((name, display_type)) = display_type {
+        let is_string = matches!(display_type, DisplayType::String | DisplayType::Stringz);
+        if is_string && !is_byte_array {
+            Err(error("Only byte arrays can be displayed as strings".into()))?
+        }
+        if display_type == DisplayType::Bytes && !is_byte_array {
+            Err(error("Only byte arrays can be displayed as bytes".into()))?
+        }
+        if !display_type.accepts_len(len) {
+            Err(error(format!(
+                "Fields displayed as `{name}` must be {}",
+                display_type.expected_len(),
+            )))?
+        }
+        if bit_offset.0 % 8 != 0 {
+            Err(error(
+                "Only fields that start at a byte boundary can use `@display`".into(),
+            ))?
+        }
+        return Ok(FieldDisplay::Typed(display_type));
+    }
+    if base.is_some() || unit.is_some() {
+        return Ok(FieldDisplay::Integer { base, unit });
+    }
+    Ok(default)
+}
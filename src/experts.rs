@@ -0,0 +1,111 @@
+//! The expert info entries that generated dissectors report problems with. They are registered on
+//! each protocol as `ProtoExpert`s, so that they can be filtered on, e.g.
+//! `toplevel.expert.unknown_enum`, and their severity can be changed in Wireshark.
+
+use std::io::Write;
+
+use crate::indent_write::IoWriteExt as _;
+
+/// A condition that generated dissectors add expert info for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expert {
+    /// A field or array is longer than the remaining bytes.
+    LengthOverrun,
+    /// The bytes consumed by a field differ from its `_size_` or known length.
+    SizeMismatch,
+    /// A fixed field, or a field constrained by a group, has a different value.
+    FixedMismatch,
+    UnknownEnum,
+    /// An array has fewer items than its count.
+    ArrayCountShort,
+    /// The dissection of an array was stopped to avoid looping for too long.
+    ArrayStopped,
+    /// A padded array is shorter than its padding.
+    PaddingShort,
+    /// Bytes are left after the top-level packet.
+    TrailingBytes,
+    /// A body matched none of the children of its packet.
+    NoChildMatched,
+    /// A Lua error was raised while dissecting a declaration.
+    DissectError,
+}
+
+impl Expert {
+    pub const ALL: &'static [Expert] = &[
+        Expert::LengthOverrun,
+        Expert::SizeMismatch,
+        Expert::FixedMismatch,
+        Expert::UnknownEnum,
+        Expert::ArrayCountShort,
+        Expert::ArrayStopped,
+        Expert::PaddingShort,
+        Expert::TrailingBytes,
+        Expert::NoChildMatched,
+        Expert::DissectError,
+    ];
+
+    /// The key of this entry in the experts table of a protocol, which is also the last part of
+    /// its filter name.
+    pub fn key(self) -> &'static str {
+        match self {
+            Expert::LengthOverrun => "length_overrun",
+            Expert::SizeMismatch => "size_mismatch",
+            Expert::FixedMismatch => "fixed_mismatch",
+            Expert::UnknownEnum => "unknown_enum",
+            Expert::ArrayCountShort => "array_count_short",
+            Expert::ArrayStopped => "array_stopped",
+            Expert::PaddingShort => "padding_short",
+            Expert::TrailingBytes => "trailing_bytes",
+            Expert::NoChildMatched => "no_child_matched",
+            Expert::DissectError => "dissect_error",
+        }
+    }
+
+    fn text(self) -> &'static str {
+        match self {
+            Expert::LengthOverrun => "Field extends past the end of the packet",
+            Expert::SizeMismatch => "Field length differs from its declared size",
+            Expert::FixedMismatch => "Field does not have its expected value",
+            Expert::UnknownEnum => "Unknown enum value",
+            Expert::ArrayCountShort => "Array has fewer items than its count",
+            Expert::ArrayStopped => "Stopped dissecting an array",
+            Expert::PaddingShort => "Padded array is shorter than its padding",
+            Expert::TrailingBytes => "Undissected bytes after the packet",
+            Expert::NoChildMatched => "Body matched no child packet",
+            Expert::DissectError => "Malformed packet",
+        }
+    }
+
+    /// The Lua expressions of the group and severity of this entry.
+    fn group_and_severity(self) -> (&'static str, &'static str) {
+        match self {
+            Expert::NoChildMatched => ("expert.group.UNDECODED", "expert.severity.NOTE"),
+            Expert::DissectError => ("expert.group.MALFORMED", "expert.severity.ERROR"),
+            _ => ("expert.group.MALFORMED", "expert.severity.WARN"),
+        }
+    }
+
+    /// Lua expression of this entry in the generated dissect functions.
+    pub fn to_lua_expr(self) -> String {
+        format!("experts.{}", self.key())
+    }
+}
+
+/// Writes the table `table_name` of all expert entries, with filter names under `filter_prefix`.
+pub fn write_experts_table(
+    writer: &mut impl Write,
+    table_name: &str,
+    filter_prefix: &str,
+) -> std::io::Result<()> {
+    writeln!(writer, "{table_name} = {{")?;
+    for expert in Expert::ALL {
+        let (group, severity) = expert.group_and_severity();
+        writeln!(
+            writer.indent(),
+            r#"{key} = ProtoExpert.new("{filter_prefix}.expert.{key}", "{text}", {group}, {severity}),"#,
+            key = expert.key(),
+            text = expert.text(),
+        )?;
+    }
+    writeln!(writer, "}}")
+}
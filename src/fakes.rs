@@ -1,53 +1,255 @@
-//! Basic fake / mock types to make sure the generated Lua code compiles.
+//! Basic fake / mock types of the Wireshark Lua API.
 //!
-//! Currently these fakes are not functional – they cannot be used to test the behavior of the
-//! callers, just whether the compilation was successful.
+//! Most of these fakes are not functional – they only make sure the generated Lua code compiles
+//! and registers its fields. `Tvb`, `Tree` and `new_pinfo` are functional enough to run the
+//! generated dissect functions on a few bytes and check what they add to the tree, and `Proto`,
+//! `Dissector` and `DissectorTable` record the protocols and the bytes handed to other dissectors.
 
 use mlua::{chunk, Lua};
 
 pub fn wireshark_lua() -> anyhow::Result<Lua> {
     let lua = Lua::new();
     lua.load(chunk! {
-        function Proto() return { fields = {} } end
+        // Like Wireshark, fails if the name of the protocol is already registered, e.g. by one of
+        // the built-in protocols.
+        Proto = { registered = { data = {}, ip = {} } }
+        setmetatable(Proto, {
+            __call = function(_, name, description)
+                if Proto.registered[string.lower(name)] then
+                    error("Proto new: there cannot be two protocols with the same name")
+                end
+                local proto = { name = name, description = description, fields = {}, experts = {} }
+                Proto.registered[string.lower(name)] = proto
+                return proto
+            end
+        })
         ProtoField = {}
-        function ProtoField.new() return {} end
+        function ProtoField.new(name, abbr, ftype, valuestring, base, mask, description)
+            // The name of the type, e.g. "UINT8", to compare it from Rust.
+            local type_name
+            for key, value in pairs(ftypes) do
+                if value == ftype then
+                    type_name = key
+                end
+            end
+            return {
+                name = name,
+                abbr = abbr,
+                ftype = ftype,
+                type_name = type_name,
+                valuestring = valuestring,
+                base = base,
+                description = description,
+            }
+        end
+        ProtoExpert = {}
+        function ProtoExpert.new(abbr, text) return { abbr = abbr, text = text } end
     })
     .exec()?;
-    lua.load(r#"DissectorTable = { get = function() return { add = function() end } end }"#)
-        .exec()?;
-    lua.load(
-        r#"
-        function Tree()
+    lua.load(chunk! {
+        // The dissectors added to each table, e.g. `DissectorTable.added["tcp.port"][80]`, and the
+        // keys and bytes looked up in them, e.g. `DissectorTable.tried["ethertype"]`.
+        DissectorTable = { added = {}, tried = {} }
+        function DissectorTable.get(name)
+            DissectorTable.added[name] = DissectorTable.added[name] or {}
+            local table = DissectorTable.added[name]
             return {
-                add = function() return Tree() end,
-                add_le = function() return Tree() end,
-                add_expert_info = function() end,
+                add = function(self, key, dissector) table[key] = dissector end,
+                try = function(self, key, tvb) DissectorTable.tried[name] = { key = key, bytes = tvb:raw() } end,
             }
         end
+        // Calls the dissector of the protocols generated into the same Lua state, and records the
+        // bytes handed to others, e.g. `Dissector.called["json"]`.
+        Dissector = { called = {} }
+        function Dissector.get(name)
+            return {
+                call = function(self, tvb, pinfo, tree)
+                    local proto = Proto.registered[name]
+                    if proto ~= nil and proto.dissector ~= nil then
+                        return proto.dissector(tvb, pinfo, tree)
+                    end
+                    Dissector.called[name] = tvb:raw()
+                end,
+            }
+        end
+    })
+    .exec()?;
+    lua.load(
+        r#"
+        -- A tree item labeled with the last string it was added with, or the name of its field. Its
+        -- children include its expert infos, labeled with "expert: " and their text.
+        function Tree(label)
+            local tree = { label = label, children = {} }
+            function tree:add(field, ...)
+                local label = type(field) == "table" and field.name or field
+                local item = Tree()
+                for _, arg in ipairs({...}) do
+                    if type(arg) == "string" then
+                        label = arg
+                    elseif type(arg) == "table" and arg.raw ~= nil and item.range == nil then
+                        item.range = arg
+                    else
+                        item.value = arg
+                    end
+                end
+                item.label = label
+                if type(field) == "table" and field.abbr ~= nil then
+                    item.field = field
+                end
+                table.insert(self.children, item)
+                return item
+            end
+            -- The first item in the tree of the field with the filter name `abbr`.
+            function tree:find(abbr)
+                for _, child in ipairs(self.children) do
+                    if child.field ~= nil and child.field.abbr == abbr then
+                        return child
+                    end
+                    local found = child:find(abbr)
+                    if found ~= nil then
+                        return found
+                    end
+                end
+            end
+            -- The value of the field of the item, which is read from its bytes unless it was added
+            -- with a value, or without bytes. Byte arrays and strings are their raw bytes.
+            function tree:field_value()
+                if self.value ~= nil then
+                    return self.value
+                elseif self.range == nil then
+                    return self.label
+                end
+                local ftype = self.field.ftype
+                if ftype == ftypes.BYTES or ftype == ftypes.STRING or ftype == ftypes.ETHER then
+                    return self.range:raw()
+                end
+                return self.little_endian and self.range:le_uint() or self.range:uint()
+            end
+            -- Items added in little endian byte order are marked with `little_endian`.
+            function tree:add_le(...)
+                local item = self:add(...)
+                item.little_endian = true
+                return item
+            end
+            -- Expert infos should be added with the experts registered by the protocol.
+            function tree:add_expert_info(group, severity, text)
+                table.insert(self.children, Tree("unregistered expert: " .. text))
+            end
+            function tree:add_proto_expert_info(expert, text)
+                assert(expert ~= nil, "Unknown expert")
+                table.insert(self.children, Tree("expert: " .. text))
+            end
+            function tree:set_generated() return self end
+            function tree:set_len() end
+            function tree:set_text(text) self.label = text end
+            function tree:append_text(text) self.label = (self.label or "") .. text end
+            function tree:prepend_text(text) self.label = text .. (self.label or "") end
+            -- The labels of the items in the tree, one per line and indented by depth.
+            function tree:dump(indent)
+                indent = indent or ""
+                local lines = {}
+                for _, child in ipairs(self.children) do
+                    table.insert(lines, indent .. tostring(child.label))
+                    local nested = child:dump(indent .. "  ")
+                    if nested ~= "" then
+                        table.insert(lines, nested)
+                    end
+                end
+                return table.concat(lines, "\n")
+            end
+            return tree
+        end
     "#,
     )
     .exec()?;
     lua.load(
         r#"
-        function Tvb(bytes)
-            local mt = {__call}
-            local t = {
-                bytes = bytes,
-                len = function() return #bytes end,
-                raw = function() return bytes end,
-                uint64 = function() return 0 end,
-                uint = function() return 0 end,
-                le_uint = function() return 0 end,
-                le_uint64 = function() return 0 end,
+        -- The 64-bit integers returned by `uint64` and wide bitfields, which are not Lua numbers.
+        local UInt64 = {}
+        UInt64.__index = UInt64
+        UInt64.__tostring = function(self) return tostring(self.value) end
+        function UInt64:tonumber() return self.value end
+
+        -- A buffer over the bytes of the string `bytes`, which were captured from a frame of
+        -- `reported_len` bytes, `#bytes` by default. Like a `TvbRange`, it can be called to get a
+        -- range of it, which raises an error if the range is out of bounds.
+        function Tvb(bytes, reported_len)
+            reported_len = reported_len or #bytes
+            local tvb = {}
+            function tvb:len() return #bytes end
+            function tvb:reported_len() return reported_len end
+            function tvb:tvb() return self end
+            function tvb:raw() return bytes end
+            function tvb:bytes() return bytes end
+            function tvb:string() return bytes end
+            function tvb:uint()
+                local value = 0
+                for k = 1, #bytes do
+                    value = value * 256 + bytes:byte(k)
+                end
+                return value
+            end
+            function tvb:le_uint() return Tvb(bytes:reverse()):uint() end
+            function tvb:uint64() return setmetatable({ value = self:uint() }, UInt64) end
+            function tvb:le_uint64() return setmetatable({ value = self:le_uint() }, UInt64) end
+            function tvb:bitfield(offset, width)
+                width = width or 1
+                local value = 0
+                for bit = offset, offset + width - 1 do
+                    value = value * 2 + ((bytes:byte(bit // 8 + 1) >> (7 - bit % 8)) & 1)
+                end
+                if width > 32 then
+                    return setmetatable({ value = value }, UInt64)
+                end
+                return value
+            end
+            setmetatable(tvb, {
+                __call = function(self, offset, len)
+                    -- Wireshark truncates the offsets of fields that start within a byte.
+                    offset = math.floor(offset or 0)
+                    -- Ranges to the end of the buffer are reported up to the end of the frame.
+                    local range_reported_len
+                    if len == nil or len == -1 then
+                        len = #bytes - offset
+                        range_reported_len = reported_len - offset
+                    end
+                    len = math.floor(len)
+                    if offset < 0 or len < 0 or offset + len > #bytes then
+                        error("Range is out of bounds")
+                    end
+                    return Tvb(bytes:sub(offset + 1, offset + len), range_reported_len)
+                end,
+            })
+            return tvb
+        end
+    "#,
+    )
+    .exec()?;
+    lua.load(
+        r#"
+        -- A column that records its text.
+        local function Column()
+            local column = { text = "" }
+            function column:set(text) self.text = text end
+            function column:append(text) self.text = self.text .. text end
+            setmetatable(column, { __tostring = function(self) return self.text end })
+            return column
+        end
+
+        DESEGMENT_ONE_MORE_SEGMENT = 0x0fffffff
+
+        -- Packet info of a fully captured frame of `len` bytes.
+        function new_pinfo(len)
+            return {
+                cols = { info = Column(), protocol = Column() },
+                len = len or 0,
+                caplen = len or 0,
+                can_desegment = 0,
             }
-            setmetatable(t, {__call = function() return t end})
-            return t
         end
     "#,
     )
     .exec()?;
-    lua.load(r#"function new_pinfo() return { cols = {} } end"#)
-        .exec()?;
     lua.load(
         r#"ftypes = {
             BOOLEAN = {},
@@ -87,10 +289,17 @@ pub fn wireshark_lua() -> anyhow::Result<Lua> {
         }"#,
     )
     .exec()?;
+    lua.load(chunk! {
+        expert = {
+            group = { CHECKSUM = 1, MALFORMED = 2, UNDECODED = 3 },
+            severity = { NOTE = 1, WARN = 2, ERROR = 3 },
+        }
+    })
+    .exec()?;
     lua.load(chunk! {
         base = {
-            NONE = {}, DEC = {}, HEX = {}, OCT = {}, DEC_HEX = {}, HEX_DEC = {},
-            RANGE_STRING = {}, UNIT_STRING = {},
+            NONE = 0, DEC = 1, HEX = 2, OCT = 3, DEC_HEX = 4, HEX_DEC = 5,
+            RANGE_STRING = 0x100, UNIT_STRING = 0x1000,
         }
     })
     .exec()?;
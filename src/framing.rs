@@ -0,0 +1,299 @@
+//! Splitting the buffers given to a protocol dissector into PDUs.
+
+use std::{collections::HashMap, io::Write};
+
+use indoc::writedoc;
+use pdl_compiler::ast::EndiannessValue;
+
+use crate::{
+    indent_write::IoWriteExt as _,
+    len_info::{BitLen, RuntimeLenInfo},
+    DeclDissectorInfo, FieldDissectorInfo,
+};
+
+/// How the buffers given to a protocol dissector are split into PDUs of the target packet.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum Framing {
+    /// Each buffer holds a single PDU. Bytes after it are reported as undissected.
+    #[default]
+    Single,
+    /// The buffers are segments of a TCP stream. PDUs are found using their length, computed from
+    /// the fields at fixed offsets in their header, and PDUs that span segments are reassembled.
+    Tcp,
+    /// Each buffer holds one or more PDUs back to back, e.g. in UDP datagrams or files. Each PDU
+    /// is shown in its own subtree, and the number of PDUs is appended to the Info column.
+    Multiple,
+}
+
+/// A scalar field at a fixed offset from the start of a PDU.
+#[derive(Debug, Clone)]
+struct HeaderField {
+    bit_offset: usize,
+    width: usize,
+    endian: EndiannessValue,
+}
+
+/// How the length of a PDU is computed from its header.
+#[derive(Debug, Clone)]
+pub struct PduLen {
+    /// The fields that the length refers to, by their filter name.
+    header_fields: Vec<(String, HeaderField)>,
+    len: RuntimeLenInfo,
+}
+
+impl PduLen {
+    /// Returns how the length of `decl` is computed, or `None` if it can't be computed from the
+    /// fields at fixed offsets, e.g. because it ends with an array without a size.
+    pub fn new(decl: &DeclDissectorInfo) -> Option<Self> {
+        let DeclDissectorInfo::Sequence { fields, .. } = decl else {
+            return Some(Self {
+                header_fields: vec![],
+                len: decl.decl_len(),
+            });
+        };
+        let mut len = RuntimeLenInfo::empty();
+        // The fields at fixed offsets, which are the ones before the first field whose length
+        // depends on the value of another field.
+        let mut fixed_fields = HashMap::new();
+        let mut fixed_offset = Some(0);
+        for field in fields {
+            let field_len = Self::field_len(field)?;
+            let RuntimeLenInfo::Bounded {
+                referenced_fields,
+                constant_factor,
+            } = &field_len
+            else {
+                return None;
+            };
+            if let (Some(offset), FieldDissectorInfo::Scalar { common, .. }) = (fixed_offset, field)
+            {
+                fixed_fields.insert(
+                    common.abbr.clone(),
+                    HeaderField {
+                        bit_offset: offset,
+                        width: constant_factor.0,
+                        endian: common.endian,
+                    },
+                );
+            }
+            fixed_offset = fixed_offset
+                .filter(|_| referenced_fields.is_empty())
+                .map(|offset| offset + constant_factor.0);
+            len.add(&field_len);
+        }
+        let RuntimeLenInfo::Bounded {
+            referenced_fields, ..
+        } = &len
+        else {
+            return None;
+        };
+        let header_fields = referenced_fields
+            .iter()
+            .map(|abbr| Some((abbr.clone(), fixed_fields.get(abbr)?.clone())))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self { header_fields, len })
+    }
+
+    /// Returns how the length of the target packet `decl` is computed, or `None` if it can't be
+    /// computed, or is always 0 bytes, so that the stream can't be split into PDUs.
+    pub fn for_target(decl: &DeclDissectorInfo) -> Option<Self> {
+        Self::new(decl)
+            .filter(|pdu_len| !pdu_len.header_fields.is_empty() || pdu_len.len.bit_offset().0 > 0)
+    }
+
+    /// The length of `field`, or `None` if it depends on more than the value of a size field.
+    fn field_len(field: &FieldDissectorInfo) -> Option<RuntimeLenInfo> {
+        match field {
+            FieldDissectorInfo::Scalar {
+                len,
+                optional_field: None,
+                ..
+            } => Some(len.clone()),
+            FieldDissectorInfo::Payload { len, .. } => Some(len.clone()),
+            FieldDissectorInfo::Typedef {
+                decl,
+                optional_field: None,
+                ..
+            } => {
+                let nested = PduLen::new(decl)?;
+                nested.header_fields.is_empty().then_some(nested.len)
+            }
+            FieldDissectorInfo::TypedefArray {
+                common,
+                decl,
+                array_info,
+            } => {
+                let item_len = PduLen::new(decl)?;
+                if !item_len.header_fields.is_empty() {
+                    return None;
+                }
+                array_info.len(&common.abbr, item_len.len.bit_offset())
+            }
+            FieldDissectorInfo::ScalarArray {
+                common,
+                item_len,
+                array_info,
+                ..
+            } => array_info.len(&common.abbr, *item_len),
+            FieldDissectorInfo::Scalar { .. } | FieldDissectorInfo::Typedef { .. } => None,
+        }
+    }
+
+    /// The number of bytes from the start of a PDU that are needed to compute its length.
+    fn header_len(&self) -> usize {
+        self.header_fields
+            .iter()
+            .map(|(_, field)| (field.bit_offset + field.width).div_ceil(8))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Writes the function `{name}_pdu_len(buffer, offset)`, which returns the length of the PDU
+    /// at `offset` in `buffer`, or `nil` if the captured bytes are too short to tell.
+    pub fn write_pdu_len_fn(
+        &self,
+        writer: &mut impl std::io::Write,
+        name: &str,
+    ) -> std::io::Result<()> {
+        writedoc!(
+            writer,
+            r#"
+            function {name}_pdu_len(buffer, offset)
+                if buffer:len() - offset < {header_len} then
+                    return nil
+                end
+                local path = ""
+                local field_values = {{}}
+            "#,
+            header_len = self.header_len(),
+        )?;
+        for (abbr, field) in &self.header_fields {
+            let byte_offset = field.bit_offset / 8;
+            let bit_offset = field.bit_offset % 8;
+            let value_expr = if bit_offset == 0 && field.width % 8 == 0 {
+                format!(
+                    "get_value(buffer(offset + {byte_offset}, {len}), {is_little_endian})",
+                    len = field.width / 8,
+                    is_little_endian = field.endian == EndiannessValue::LittleEndian,
+                )
+            } else {
+                format!(
+                    "buffer(offset + {byte_offset}, {len}):bitfield({bit_offset}, {width})",
+                    len = (bit_offset + field.width).div_ceil(8),
+                    width = field.width,
+                )
+            };
+            // Wider values are read as `UInt64`s, which `math.ceil` does not accept.
+            let to_number = if field.width > 32 { ":tonumber()" } else { "" };
+            writeln!(
+                writer.indent(),
+                r#"field_values[path .. ".{abbr}"] = {value_expr}{to_number}"#
+            )?;
+        }
+        writedoc!(
+            writer,
+            r#"
+                return math.ceil({len_expr})
+            end
+            "#,
+            len_expr = self.len.to_lua_expr(),
+        )
+    }
+}
+
+/// Writes the dissector function of the protocol `{name}_protocol`, which splits the buffer into
+/// PDUs with `{name}_pdu_len` and dissects each of them with `dissect_pdu_fn`. With `append_info`,
+/// `dissect_pdu_fn` is told whether to append to the Info column, for the PDUs after the first.
+pub fn write_tcp_dissector(
+    writer: &mut impl std::io::Write,
+    name: &str,
+    dissect_pdu_fn: &str,
+    append_info: bool,
+) -> std::io::Result<()> {
+    writedoc!(
+        writer,
+        r#"
+        function {name}_protocol.dissector(buffer, pinfo, tree)
+            local offset = 0
+            while offset < buffer:len() do
+                local pdu_len = {name}_pdu_len(buffer, offset)
+                if pdu_len == nil or offset + pdu_len > buffer:len() then
+                    if pinfo.can_desegment > 0 then
+                        -- Ask for the rest of the PDU, or one more segment if its length isn't known yet
+                        pinfo.desegment_offset = offset
+                        pinfo.desegment_len = pdu_len and (offset + pdu_len - buffer:len()) or DESEGMENT_ONE_MORE_SEGMENT
+                        return buffer:len()
+                    end
+                    pdu_len = buffer:len() - offset
+                end
+                {dissect_pdu_fn}(buffer(offset, pdu_len):tvb(), pinfo, tree{append_info_arg})
+                offset = offset + pdu_len
+            end
+            return offset
+        end
+        "#,
+        append_info_arg = if append_info { ", offset > 0" } else { "" },
+    )
+}
+
+/// Writes the dissector function of the protocol `{name}_protocol`, which dissects PDUs with
+/// `dissect_pdu_fn` until the buffer is consumed. `trailing_bytes` is the Lua expression of the
+/// expert reported when a PDU consumes no bytes. `append_info` is as for [`write_tcp_dissector`].
+pub fn write_multiple_dissector(
+    writer: &mut impl std::io::Write,
+    name: &str,
+    dissect_pdu_fn: &str,
+    trailing_bytes: &str,
+    append_info: bool,
+) -> std::io::Result<()> {
+    writedoc!(
+        writer,
+        r#"
+        function {name}_protocol.dissector(buffer, pinfo, tree)
+            local offset = 0
+            local pdu_count = 0
+            while offset < buffer:len() do
+                local pdu_len = {dissect_pdu_fn}(buffer(offset):tvb(), pinfo, tree{append_info_arg})
+                pdu_count = pdu_count + 1
+                if pdu_len == 0 then
+                    tree:add_proto_expert_info({trailing_bytes}, "Error: " .. (buffer:len() - offset) .. " undissected bytes remaining")
+                    break
+                end
+                offset = offset + pdu_len
+            end
+            pinfo.cols.info:append(" (" .. pdu_count .. (pdu_count == 1 and " message)" or " messages)"))
+            return offset
+        end
+        "#,
+        append_info_arg = if append_info { ", offset > 0" } else { "" },
+    )
+}
+
+impl crate::ArrayFieldDissectorInfo {
+    /// The length of an array with items of `item_len`, or `None` if it can't be computed from its
+    /// count or size field.
+    fn len(&self, abbr: &str, item_len: BitLen) -> Option<RuntimeLenInfo> {
+        if let Some(count) = self.count {
+            Some(RuntimeLenInfo::fixed(BitLen(count * item_len.0)))
+        } else if self.has_size_field {
+            let mut len = RuntimeLenInfo::empty();
+            let modifier = self.size_modifier.unwrap_or_default();
+            len.add_len_field(format!("{abbr}_size"), BitLen(modifier * 8));
+            Some(len)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,701 @@
+//! Generating dissectors from PDL declarations given as a file, as source text, or as a file
+//! already analyzed by `pdl_compiler`, for use in other tools and build scripts.
+
+use std::{
+    io::{Read as _, Write},
+    path::{Path, PathBuf},
+};
+
+use ::indent_write::io::IndentWriter;
+use codespan_reporting::diagnostic::Diagnostic;
+use indoc::writedoc;
+use pdl_compiler::{
+    analyzer::{self, Scope},
+    ast::{Annotation, Decl, DeclDesc, FileId, SourceDatabase},
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    check_child_constraints, check_unsupported,
+    diagnostics::Diagnostics,
+    framing::PduLen,
+    info::InfoTemplate,
+    ir, list,
+    naming::{is_valid_filter_name, ProtocolNames},
+    utils::lua_string,
+    validate_annotations, AbbrStyle, Args, Binding, DeclExt as _, DissectorOptions, Framing,
+};
+
+/// Where the PDL declarations of a [`Generator`] come from.
+#[derive(Debug, Clone)]
+enum PdlSource {
+    File(PathBuf),
+    Text { name: String, text: String },
+    Analyzed(analyzer::ast::File),
+}
+
+/// Generates a Wireshark dissector from PDL declarations. Unlike [`crate::run`], this doesn't need
+/// command line arguments or files on disk, and has no global side effects.
+///
+/// ```
+/// # use pdl_compiler::ast::SourceDatabase;
+/// let mut lua = vec![];
+/// let warnings = pdl_dissector::Generator::from_source(
+///     "example.pdl",
+///     "little_endian_packets\npacket Example { value: 16 }",
+/// )
+/// .target("Example")
+/// .protocol_name("EXAMPLE")
+/// .generate(&mut SourceDatabase::new(), &mut lua)
+/// .unwrap();
+/// assert!(warnings.as_slice().is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Generator {
+    source: PdlSource,
+    target_packets: Vec<String>,
+    protocol_name: Option<String>,
+    protocol_full_name: Option<String>,
+    filter_prefix: Option<String>,
+    framing: Framing,
+    tcp_ports: Vec<u16>,
+    udp_ports: Vec<u16>,
+    info: Option<String>,
+    module: bool,
+    options: DissectorOptions,
+    /// The names of the protocols of the target packets when they are bound to fields of another
+    /// file, by packet.
+    bound_protocol_names: Vec<(String, String)>,
+}
+
+/// The declarations whose Lua values are returned by a dissector generated as a module.
+#[derive(Debug, Default)]
+struct ModuleExports {
+    /// The target packets, which each have a protocol with its fields and experts.
+    protocols: Vec<String>,
+    /// The packets, structs, groups and custom fields, which each have a dissect function.
+    dissect_fns: Vec<String>,
+    enums: Vec<String>,
+}
+
+impl Generator {
+    fn new(source: PdlSource) -> Self {
+        Self {
+            source,
+            target_packets: vec![],
+            protocol_name: None,
+            protocol_full_name: None,
+            filter_prefix: None,
+            framing: Framing::default(),
+            tcp_ports: vec![],
+            udp_ports: vec![],
+            info: None,
+            module: false,
+            options: DissectorOptions::default(),
+            bound_protocol_names: vec![],
+        }
+    }
+
+    /// Generates from the PDL file at `path`, which is read when generating.
+    pub fn from_file(path: impl Into<PathBuf>) -> Self {
+        Self::new(PdlSource::File(path.into()))
+    }
+
+    /// Generates from PDL source text. `name` is the file name shown in diagnostics.
+    pub fn from_source(name: impl Into<String>, text: impl Into<String>) -> Self {
+        Self::new(PdlSource::Text {
+            name: name.into(),
+            text: text.into(),
+        })
+    }
+
+    /// Generates from a file already analyzed by `pdl_compiler`. Its source must be in the
+    /// `SourceDatabase` given to [`Generator::generate`] for diagnostics to show its code.
+    pub fn from_analyzed(file: analyzer::ast::File) -> Self {
+        Self::new(PdlSource::Analyzed(file))
+    }
+
+    /// Generates from the file and with the options of command line arguments. The PDL file `-`
+    /// is read from stdin.
+    pub fn from_args(args: Args) -> Result<Self, Diagnostics> {
+        let Some(pdl_file) = args.pdl_file else {
+            Err(Diagnostic::error().with_message("PDL file must be specified"))?
+        };
+        let source = if pdl_file == Path::new("-") {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text).map_err(|err| {
+                Diagnostic::error().with_message(format!("Unable to read stdin: {err}"))
+            })?;
+            PdlSource::Text {
+                name: "<stdin>".into(),
+                text,
+            }
+        } else {
+            PdlSource::File(pdl_file)
+        };
+        Ok(Self {
+            target_packets: args.target_packets,
+            protocol_name: args.protocol_name,
+            protocol_full_name: args.protocol_full_name,
+            filter_prefix: args.filter_prefix,
+            framing: args.framing,
+            tcp_ports: args.tcp_ports,
+            udp_ports: args.udp_ports,
+            info: args.info,
+            module: args.module,
+            options: args.options,
+            ..Self::new(source)
+        })
+    }
+
+    /// Adds a packet to generate a protocol for, or `_all_` for all packets.
+    pub fn target(mut self, packet: impl Into<String>) -> Self {
+        self.target_packets.push(packet.into());
+        self
+    }
+
+    /// Adds packets to generate protocols for.
+    pub fn targets(mut self, packets: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.target_packets
+            .extend(packets.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the short name of the protocol, like `--protocol-name`.
+    pub fn protocol_name(mut self, name: impl Into<String>) -> Self {
+        self.protocol_name = Some(name.into());
+        self
+    }
+
+    /// Sets the descriptive name of the protocol, like `--protocol-full-name`.
+    pub fn protocol_full_name(mut self, name: impl Into<String>) -> Self {
+        self.protocol_full_name = Some(name.into());
+        self
+    }
+
+    /// Sets the prefix of the display filter names of all fields, like `--filter-prefix`.
+    pub fn filter_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.filter_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets how buffers are split into PDUs, like `--framing`.
+    pub fn framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Adds a TCP port to register the protocol on, like `--tcp-port`.
+    pub fn tcp_port(mut self, port: u16) -> Self {
+        self.tcp_ports.push(port);
+        self
+    }
+
+    /// Adds a UDP port to register the protocol on, like `--udp-port`.
+    pub fn udp_port(mut self, port: u16) -> Self {
+        self.udp_ports.push(port);
+        self
+    }
+
+    /// Sets the template of the text shown in the Info column for each PDU, like `--info`.
+    pub fn info(mut self, template: impl Into<String>) -> Self {
+        self.info = Some(template.into());
+        self
+    }
+
+    /// Replaces all options that control how declarations are translated.
+    pub fn options(mut self, options: DissectorOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets how names are written in display filter names, like `--abbr-style`.
+    pub fn abbr_style(mut self, abbr_style: AbbrStyle) -> Self {
+        self.options.abbr_style = abbr_style;
+        self
+    }
+
+    /// Displays a scalar field as a boolean, like `--boolean-field <decl_id>.<field_id>`.
+    pub fn boolean_field(mut self, decl_id: &str, field_id: &str) -> Self {
+        self.options
+            .boolean_fields
+            .push(format!("{decl_id}.{field_id}"));
+        self
+    }
+
+    /// Generates a Lua module that returns the protocols, dissect functions, enums and fields,
+    /// like `--module`.
+    pub fn module(mut self, module: bool) -> Self {
+        self.module = module;
+        self
+    }
+
+    /// Shows each byte of 8-bit arrays as a separate item, like `--expand-byte-arrays`.
+    pub fn expand_byte_arrays(mut self, expand: bool) -> Self {
+        self.options.expand_byte_arrays = expand;
+        self
+    }
+
+    /// Dissects a payload or byte array as a packet of another PDL file, like `--bind`.
+    pub fn bind(mut self, binding: Binding) -> Self {
+        self.options.bindings.push(binding);
+        self
+    }
+
+    /// The files read when generating, i.e. the PDL file and the files of its bindings.
+    pub(crate) fn input_files(&self) -> Vec<PathBuf> {
+        let main_file = match &self.source {
+            PdlSource::File(pdl_file) => Some(pdl_file.clone()),
+            PdlSource::Text { .. } | PdlSource::Analyzed(_) => None,
+        };
+        main_file
+            .into_iter()
+            .chain(
+                self.options
+                    .bindings
+                    .iter()
+                    .map(|binding| binding.pdl_file.clone()),
+            )
+            .collect()
+    }
+
+    /// Generates the dissector into `writer`, which is only written to if there are no errors.
+    /// Returns the warnings found in the PDL declarations, which don't stop the dissector from
+    /// being generated.
+    ///
+    /// The dissector starts with a header naming the version of this tool and the SHA-256 hash of
+    /// the PDL files and options it was generated from.
+    pub fn generate(
+        &self,
+        sources: &mut SourceDatabase,
+        writer: &mut impl std::io::Write,
+    ) -> Result<Diagnostics, Diagnostics> {
+        // The protocols are generated into a buffer first, so that nothing is written on errors.
+        let mut protocols = vec![];
+        let mut inputs = Sha256::new();
+        inputs.update(format!(
+            "{:?}",
+            (
+                &self.target_packets,
+                &self.protocol_name,
+                &self.protocol_full_name,
+                &self.filter_prefix,
+                self.framing,
+                &self.tcp_ports,
+                &self.udp_ports,
+                &self.info,
+                self.module,
+                &self.options,
+            )
+        ));
+        let mut exports = ModuleExports::default();
+        let mut warnings =
+            self.write_protocols(sources, &mut inputs, &mut exports, &mut protocols)?;
+
+        // The packets bound to fields are generated from their own files, each in a namespace so
+        // that declarations with the same name in different files don't clash.
+        for (pdl_file, bound_generator) in self.bound_generators() {
+            writedoc!(
+                protocols,
+                r#"
+                -- Namespace of {pdl_file}
+                do
+                    local _ENV = setmetatable({{}}, {{ __index = _ENV }})
+                "#,
+                pdl_file = pdl_file.display(),
+            )?;
+            warnings.extend(bound_generator.write_protocols(
+                sources,
+                &mut inputs,
+                &mut ModuleExports::default(),
+                &mut IndentWriter::new("    ", &mut protocols),
+            )?);
+            writeln!(protocols, "end")?;
+        }
+        let inputs_hash: String = inputs
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        writedoc!(
+            writer,
+            r#"
+            -- Generated by pdl-dissector {version}. Do not edit, regenerate it instead.
+            -- Inputs: sha256:{inputs_hash}
+            "#,
+            version = env!("CARGO_PKG_VERSION"),
+        )?;
+        if self.module {
+            // The globals of the dissector are kept in the environment of the module.
+            writeln!(
+                writer,
+                "local _ENV = setmetatable({{}}, {{ __index = _G }})"
+            )?;
+        }
+        write!(writer, "{}", include_str!("utils.lua"))?;
+        writer.write_all(&protocols)?;
+        if self.module {
+            exports.write_return(writer)?;
+        }
+        Ok(Diagnostics::from(warnings))
+    }
+
+    /// Analyzes the PDL files and checks the options without generating the dissector, e.g. to
+    /// report PDL constructs that cannot be dissected. Returns the warnings found in the PDL
+    /// files.
+    pub fn check(&self, sources: &mut SourceDatabase) -> Result<Diagnostics, Diagnostics> {
+        let mut warnings = self.analyze(sources)?.1;
+        for (_, bound_generator) in self.bound_generators() {
+            warnings.extend(bound_generator.analyze(sources)?.1);
+        }
+        Ok(Diagnostics::from(warnings))
+    }
+
+    /// Writes the declarations of the PDL file into `writer`, with child packets and structs under
+    /// their parents. The options and targets of this generator are not used.
+    pub fn list(
+        &self,
+        sources: &mut SourceDatabase,
+        writer: &mut impl std::io::Write,
+    ) -> Result<(), Diagnostics> {
+        list::write_declarations(&self.parse(sources)?, writer)
+    }
+
+    /// Builds the backend-neutral representation of the PDL file and of the protocols of the
+    /// target packets. Bound files are not included; their packets are only named by the
+    /// handoffs of the fields bound to them.
+    pub fn ir(&self, sources: &mut SourceDatabase) -> Result<ir::File, Diagnostics> {
+        let analyzed_file = self.analyze(sources)?.0;
+        let scope = Scope::new(&analyzed_file)?;
+        let mut protocols = vec![];
+        for target_packet in &self.target_packets {
+            let packets = if target_packet == "_all_" {
+                analyzed_file
+                    .declarations
+                    .iter()
+                    .filter(|decl| matches!(decl.desc, DeclDesc::Packet { .. }))
+                    .filter_map(|decl| decl.id())
+                    .collect()
+            } else {
+                vec![target_packet.as_str()]
+            };
+            for packet in packets {
+                let ProtocolNames {
+                    short_name,
+                    full_name,
+                    filter_prefix,
+                } = self.protocol_names(packet)?;
+                protocols.push(ir::Protocol {
+                    packet: packet.to_owned(),
+                    short_name,
+                    full_name,
+                    filter_prefix,
+                    framing: self.framing,
+                    tcp_ports: self.tcp_ports.clone(),
+                    udp_ports: self.udp_ports.clone(),
+                    info: self.info.clone(),
+                });
+            }
+        }
+        let declarations = analyzed_file
+            .declarations
+            .iter()
+            .filter(|decl| !matches!(decl.desc, DeclDesc::Test { .. }))
+            .map(|decl| ir::Decl::from(&decl.to_dissector_info(&scope, &self.options)))
+            .collect();
+        Ok(ir::File {
+            version: ir::VERSION,
+            protocols,
+            declarations,
+        })
+    }
+
+    /// Parses and analyzes the PDL file.
+    fn parse(&self, sources: &mut SourceDatabase) -> Result<analyzer::ast::File, Diagnostics> {
+        Ok(match &self.source {
+            PdlSource::File(pdl_file) => {
+                let file = pdl_compiler::parser::parse_file(
+                    sources,
+                    pdl_file
+                        .to_str()
+                        .expect("pdl_file path should be a valid string"),
+                )?;
+                analyzer::analyze(&file)?
+            }
+            PdlSource::Text { name, text } => {
+                let file = pdl_compiler::parser::parse_inline(sources, name, text.clone())?;
+                analyzer::analyze(&file)?
+            }
+            PdlSource::Analyzed(file) => file.clone(),
+        })
+    }
+
+    /// The generators of the files of the packets bound to fields, with the packets of each file
+    /// as targets, whose protocols are named after the first field each packet is bound to.
+    fn bound_generators(&self) -> Vec<(PathBuf, Generator)> {
+        let mut bound_files: Vec<(&PathBuf, Vec<&Binding>)> = vec![];
+        for binding in &self.options.bindings {
+            match bound_files
+                .iter_mut()
+                .find(|(pdl_file, _)| **pdl_file == binding.pdl_file)
+            {
+                Some((_, bindings))
+                    if bindings.iter().any(|other| other.packet == binding.packet) => {}
+                Some((_, bindings)) => bindings.push(binding),
+                None => bound_files.push((&binding.pdl_file, vec![binding])),
+            }
+        }
+        bound_files
+            .into_iter()
+            .map(|(pdl_file, bindings)| {
+                // Boolean fields and bindings refer to the declarations of the main file only.
+                let mut generator = Generator::from_file(pdl_file.clone())
+                    .targets(bindings.iter().map(|binding| binding.packet.clone()))
+                    .abbr_style(self.options.abbr_style)
+                    .expand_byte_arrays(self.options.expand_byte_arrays);
+                generator.bound_protocol_names = bindings
+                    .iter()
+                    .map(|binding| (binding.packet.clone(), binding.protocol_name()))
+                    .collect();
+                (pdl_file.clone(), generator)
+            })
+            .collect()
+    }
+
+    /// Parses and analyzes the PDL file, and checks that it can be dissected with the options and
+    /// targets of this generator. Returns the analyzed file and the warnings found in it.
+    fn analyze(
+        &self,
+        sources: &mut SourceDatabase,
+    ) -> Result<(analyzer::ast::File, Vec<Diagnostic<FileId>>), Diagnostics> {
+        let analyzed_file = self.parse(sources)?;
+        let scope = Scope::new(&analyzed_file)?;
+        for target_packet in &self.target_packets {
+            let exists = target_packet == "_all_"
+                || analyzed_file
+                    .declarations
+                    .iter()
+                    .any(|decl| get_desc_id(&decl.desc).as_ref() == Some(target_packet));
+            if !exists {
+                Err(Diagnostic::error()
+                    .with_message(format!("Unable to find declaration {target_packet:?}")))?
+            }
+        }
+        let has_protocol_names = self.protocol_name.is_some()
+            || self.protocol_full_name.is_some()
+            || self.filter_prefix.is_some()
+            || !self.tcp_ports.is_empty()
+            || !self.udp_ports.is_empty()
+            || self.info.is_some();
+        if has_protocol_names
+            && (self.target_packets.len() > 1
+                || self
+                    .target_packets
+                    .first()
+                    .is_some_and(|target| target == "_all_"))
+        {
+            Err(Diagnostic::error().with_message(
+                "Protocol names, filter prefix, ports and Info template can only be specified with a single target packet",
+            ))?
+        }
+
+        self.options.validate(&scope)?;
+        check_unsupported(&analyzed_file)?;
+        validate_annotations(&analyzed_file, &scope, &self.options)?;
+        let warnings = check_child_constraints(&analyzed_file, &scope, &self.options);
+        Ok((analyzed_file, warnings))
+    }
+
+    /// Generates the protocols of the target packets from a single PDL file into `writer`, adds
+    /// the PDL file to the hash of the `inputs`, and its declarations to the `exports`. Returns the
+    /// warnings found in the PDL file.
+    fn write_protocols(
+        &self,
+        sources: &mut SourceDatabase,
+        inputs: &mut Sha256,
+        exports: &mut ModuleExports,
+        writer: &mut impl std::io::Write,
+    ) -> Result<Vec<Diagnostic<FileId>>, Diagnostics> {
+        if self.target_packets.is_empty() {
+            Err(Diagnostic::error().with_message("Target packet must be specified"))?
+        }
+        let (analyzed_file, warnings) = self.analyze(sources)?;
+        match sources.get(analyzed_file.file) {
+            Ok(source) => inputs.update(source.source()),
+            // Files analyzed elsewhere may not be in `sources`.
+            Err(_) => inputs.update(format!("{analyzed_file:?}")),
+        }
+        let scope = Scope::new(&analyzed_file)?;
+        for decl in &analyzed_file.declarations {
+            match &decl.desc {
+                DeclDesc::Enum { id, .. } => exports.enums.push(id.clone()),
+                DeclDesc::Packet { id, .. }
+                | DeclDesc::Struct { id, .. }
+                | DeclDesc::Group { id, .. }
+                | DeclDesc::CustomField { id, .. } => exports.dissect_fns.push(id.clone()),
+                DeclDesc::Checksum { .. } | DeclDesc::Test { .. } => {}
+            }
+        }
+
+        for target_packet in &self.target_packets {
+            for decl in analyzed_file.declarations.iter() {
+                let decl_dissector_info = decl.to_dissector_info(&scope, &self.options);
+                decl_dissector_info.write_proto_fields(writer)?;
+                decl_dissector_info.write_dissect_fn(writer)?;
+            }
+            if target_packet == "_all_" {
+                for decl in analyzed_file.declarations.iter() {
+                    if matches!(decl.desc, DeclDesc::Packet { .. }) {
+                        let decl_name = decl.id().unwrap();
+                        self.generate_for_decl(decl_name, decl, &scope, writer)?;
+                        exports.protocols.push(decl_name.to_owned());
+                    }
+                }
+            } else {
+                let target_decl = analyzed_file.declarations.iter().find(|decl| {
+                    get_desc_id(&decl.desc)
+                        .map(|id| &id == target_packet)
+                        .unwrap_or(false)
+                });
+                // Targets were checked to exist when analyzing.
+                if let Some(decl) = target_decl {
+                    self.generate_for_decl(target_packet, decl, &scope, writer)?;
+                    exports.protocols.push(target_packet.clone());
+                }
+            }
+        }
+        Ok(warnings)
+    }
+
+    fn protocol_names(&self, decl_name: &str) -> Result<ProtocolNames, Diagnostics> {
+        let bound_name = self
+            .bound_protocol_names
+            .iter()
+            .find(|(packet, _)| packet == decl_name)
+            .map(|(_, name)| name.clone());
+        let short_name = self
+            .protocol_name
+            .clone()
+            .or_else(|| bound_name.clone())
+            .unwrap_or_else(|| decl_name.to_string());
+        // Full names are also registered globally, so bound packets are named with their field.
+        let full_name = self
+            .protocol_full_name
+            .clone()
+            .unwrap_or_else(|| match &bound_name {
+                Some(name) => format!("{decl_name} ({name})"),
+                None => short_name.clone(),
+            });
+        // Wireshark names the protocol in display filters after its lowercased short name, so the
+        // fields are under that name regardless of the abbreviation style.
+        let filter_prefix = self
+            .filter_prefix
+            .clone()
+            .unwrap_or_else(|| short_name.to_lowercase());
+        if !is_valid_filter_name(&short_name.to_lowercase()) {
+            Err(Diagnostic::error().with_message(format!(
+                "Protocol name {short_name:?} can only contain letters, digits, '-', '_' and '.'"
+            )))?
+        }
+        if !is_valid_filter_name(&filter_prefix) {
+            Err(Diagnostic::error().with_message(format!(
+                "Filter prefix {filter_prefix:?} can only contain letters, digits, '-', '_' and '.'"
+            )))?
+        }
+        Ok(ProtocolNames {
+            short_name,
+            full_name,
+            filter_prefix,
+        })
+    }
+
+    fn generate_for_decl(
+        &self,
+        decl_name: &str,
+        decl: &Decl<analyzer::ast::Annotation>,
+        scope: &Scope<analyzer::ast::Annotation>,
+        writer: &mut impl std::io::Write,
+    ) -> Result<(), Diagnostics> {
+        let names = self.protocol_names(decl_name)?;
+        let target_dissector_info = decl.to_dissector_info(scope, &self.options);
+
+        writedoc!(
+            writer,
+            r#"
+            -- Protocol definition for "{decl_name}"
+            {decl_name}_protocol = Proto("{short_name}",  "{full_name}")
+            "#,
+            short_name = names.short_name,
+            full_name = names.escaped_full_name(),
+        )?;
+
+        if self.framing == Framing::Tcp && PduLen::for_target(&target_dissector_info).is_none() {
+            Err(Diagnostic::error()
+                .with_message(format!(
+                    "The length of `{decl_name}` cannot be computed from its header, which is needed to split TCP streams into PDUs"
+                ))
+                .with_labels(vec![decl.loc.primary()])
+                .with_notes(vec![
+                    "The length of each field must be fixed or given by a `_size_` field at a fixed offset".into(),
+                ]))?
+        }
+        let info_expr = match &self.info {
+            Some(template) => Some(
+                InfoTemplate::parse(template)?
+                    .to_lua_expr(&target_dissector_info, &names.filter_prefix)?,
+            ),
+            None => None,
+        };
+        target_dissector_info.write_main_dissector(
+            writer,
+            &names,
+            self.framing,
+            info_expr.as_deref(),
+        )?;
+        for (table, ports) in [("tcp.port", &self.tcp_ports), ("udp.port", &self.udp_ports)] {
+            for port in ports {
+                writeln!(
+                    writer,
+                    r#"DissectorTable.get("{table}"):add({port}, {decl_name}_protocol)"#
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ModuleExports {
+    /// Writes the statement returning the table of the module, e.g.
+    /// `protocols = { Message = Message_protocol }` with the dissect functions, enums, fields and
+    /// experts by the name of their declaration.
+    fn write_return(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        let tables: [(&str, &[String], &str); 5] = [
+            ("protocols", &self.protocols, "_protocol"),
+            ("dissect", &self.dissect_fns, "_dissect"),
+            ("enums", &self.enums, "_enum"),
+            ("fields", &self.protocols, "_protocol_fields_table"),
+            ("experts", &self.protocols, "_protocol_experts"),
+        ];
+        writeln!(writer, "return {{")?;
+        for (table, names, suffix) in tables {
+            writeln!(writer, "    {table} = {{")?;
+            for name in names {
+                writeln!(writer, "        [{}] = {name}{suffix},", lua_string(name))?;
+            }
+            writeln!(writer, "    }},")?;
+        }
+        writeln!(writer, "}}")
+    }
+}
+
+fn get_desc_id<A: Annotation>(desc: &DeclDesc<A>) -> Option<String> {
+    match desc {
+        DeclDesc::Checksum { id, .. }
+        | DeclDesc::CustomField { id, .. }
+        | DeclDesc::Enum { id, .. }
+        | DeclDesc::Packet { id, .. }
+        | DeclDesc::Struct { id, .. }
+        | DeclDesc::Group { id, .. } => Some(id.clone()),
+        DeclDesc::Test { .. } => None,
+    }
+}
@@ -0,0 +1,240 @@
+//! Handing the bytes of payloads and byte arrays to other Wireshark dissectors. This is chosen
+//! with annotations in the comment on the same line as the field, e.g.
+//! `_payload_, // @dissector=ip` or `_payload_, // @dissector_table=ethertype @key=ether_type`, or
+//! with a [`Binding`] to a packet of another PDL file.
+
+use std::{path::PathBuf, str::FromStr};
+
+use codespan_reporting::diagnostic::Diagnostic;
+use pdl_compiler::{
+    analyzer::ast::{Decl, Field, File},
+    ast::{DeclDesc, FieldDesc, FileId},
+};
+
+use crate::{
+    comments::{find_comments_on_same_line, parse_annotations, unwrap_comment},
+    utils::lua_string,
+    DissectorOptions, Scope,
+};
+
+/// The annotation keys that choose a dissector to hand the field to.
+pub const HANDOFF_KEYS: &[&str] = &["dissector", "dissector_table", "key"];
+
+/// A Wireshark dissector that the bytes of a field are handed to.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Handoff {
+    /// The dissector registered with the given name, e.g. `ip`, set with `@dissector=<name>`.
+    Dissector(String),
+    /// The dissector registered in a dissector table for the value of a field of the same
+    /// declaration, set with `@dissector_table=<table> @key=<field>`.
+    Table {
+        table: String,
+        /// Filter name segment of the key field.
+        key_abbr: String,
+    },
+}
+
+impl Handoff {
+    /// Writes the Lua code that hands the `field_len` bytes at `i` to this dissector, which
+    /// adds its tree into `tree`.
+    pub fn write_call(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
+        let call = match self {
+            Handoff::Dissector(name) => format!(
+                "Dissector.get({name}):call(buffer(i, field_len):tvb(), pinfo, tree)",
+                name = lua_string(name),
+            ),
+            Handoff::Table { table, key_abbr } => format!(
+                r#"DissectorTable.get({table}):try(field_values[path .. ".{key_abbr}"], buffer(i, field_len):tvb(), pinfo, tree)"#,
+                table = lua_string(table),
+            ),
+        };
+        writeln!(writer, "if field_len > 0 then")?;
+        writeln!(writer, "    {call}")?;
+        writeln!(writer, "end")
+    }
+}
+
+/// A payload or byte array that is dissected as a packet of another PDL file, set with
+/// `--bind <declaration>.<field>=<file>:<packet>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub decl_id: String,
+    /// The name of the field, which is `_payload_` or `_body_` for payloads.
+    pub field_id: String,
+    pub pdl_file: PathBuf,
+    pub packet: String,
+}
+
+impl Binding {
+    /// Whether this binding is for `field` of `decl`.
+    fn is_for(&self, decl: &Decl, field: &Field) -> bool {
+        let field_id = match &field.desc {
+            FieldDesc::Payload { .. } => Some("_payload_"),
+            FieldDesc::Body => Some("_body_"),
+            _ => field.id(),
+        };
+        decl.id() == Some(self.decl_id.as_str()) && field_id == Some(self.field_id.as_str())
+    }
+
+    /// The name of the protocol generated for the bound packet, e.g. `Frame.payload` for
+    /// `Frame._payload_`. Wireshark registers protocol names globally, so it is named after the
+    /// field rather than the packet, whose name may be taken by a built-in protocol such as `ip`.
+    pub fn protocol_name(&self) -> String {
+        format!("{}.{}", self.decl_id, self.field_id.trim_matches('_'))
+    }
+
+    /// The dissector of the protocol generated for the bound packet, which Wireshark registers
+    /// under the lowercased name of the protocol. A packet bound to several fields has a single
+    /// protocol, named after the first of them.
+    fn handoff(&self, bindings: &[Binding]) -> Handoff {
+        let first = bindings
+            .iter()
+            .find(|other| other.pdl_file == self.pdl_file && other.packet == self.packet)
+            .unwrap_or(self);
+        Handoff::Dissector(first.protocol_name().to_lowercase())
+    }
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = || {
+            let (field, target) = s.split_once('=')?;
+            let (decl_id, field_id) = field.split_once('.')?;
+            // Split at the last `:`, which allows Windows paths such as `C:\app.pdl`.
+            let (pdl_file, packet) = target.rsplit_once(':')?;
+            [decl_id, field_id, pdl_file, packet]
+                .iter()
+                .all(|part| !part.is_empty())
+                .then(|| Binding {
+                    decl_id: decl_id.into(),
+                    field_id: field_id.into(),
+                    pdl_file: pdl_file.into(),
+                    packet: packet.into(),
+                })
+        };
+        parse().ok_or_else(|| {
+            format!("{s:?} should be written as `<declaration>.<field>=<file>:<packet>`")
+        })
+    }
+}
+
+/// Returns the dissector that the bytes of `field` in `decl` are handed to, or an error if its
+/// handoff annotations or binding are invalid or cannot be used with this field.
+pub fn field_handoff(
+    field: &Field,
+    decl: &Decl,
+    scope: &Scope,
+    options: &DissectorOptions,
+    file: &File,
+) -> Result<Option<Handoff>, Diagnostic<FileId>> {
+    let comment = find_comments_on_same_line(file, &field.loc);
+    let annotations = comment
+        .map(|comment| parse_annotations(unwrap_comment(&comment.text)).1)
+        .unwrap_or_default();
+    let error = |message: String| {
+        let labels = match comment {
+            Some(comment) => vec![
+                comment.loc.primary(),
+                field.loc.secondary().with_message("in this field"),
+            ],
+            // Fields bound with `--bind` may not have a comment.
+            None => vec![field.loc.primary()],
+        };
+        Diagnostic::error()
+            .with_message(message)
+            .with_labels(labels)
+    };
+
+    let mut dissector = None;
+    let mut table = None;
+    let mut key = None;
+    for annotation in annotations
+        .iter()
+        .filter(|annotation| HANDOFF_KEYS.contains(&annotation.key.as_str()))
+    {
+        let key_name = annotation.key.as_str();
+        let Some(value) = annotation.value.as_deref() else {
+            Err(error(format!(
+                "Annotation `@{key_name}` requires a value, e.g. `@{key_name}=<value>`"
+            )))?
+        };
+        match key_name {
+            "dissector" => dissector = Some(value),
+            "dissector_table" => table = Some(value),
+            _ => key = Some(value),
+        }
+    }
+    let binding = options
+        .bindings
+        .iter()
+        .find(|binding| binding.is_for(decl, field));
+    let handoff = match (dissector, table, key) {
+        (None, None, None) => match binding {
+            Some(binding) => binding.handoff(&options.bindings),
+            None => return Ok(None),
+        },
+        _ if binding.is_some() => Err(error(
+            "Fields bound with `--bind` cannot also use `@dissector` or `@dissector_table`".into(),
+        ))?,
+        (Some(name), None, None) => Handoff::Dissector(name.to_string()),
+        (None, Some(table), Some(key)) => {
+            let key_field = decl
+                .fields()
+                .take_while(|other| !std::ptr::eq(*other, field))
+                .find(|other| other.id() == Some(key))
+                .ok_or_else(|| {
+                    error(format!(
+                        "Unable to find the key field `{key}` before this field in `{}`",
+                        decl.id().unwrap_or_default()
+                    ))
+                })?;
+            let is_enum = |type_id: &str| {
+                scope
+                    .typedef
+                    .get(type_id)
+                    .is_some_and(|decl| matches!(decl.desc, DeclDesc::Enum { .. }))
+            };
+            match &key_field.desc {
+                FieldDesc::Scalar { .. } => {}
+                FieldDesc::Typedef { type_id, .. } if is_enum(type_id) => {}
+                _ => Err(error(format!(
+                    "The key field `{key}` must be a scalar or enum field"
+                )))?,
+            }
+            Handoff::Table {
+                table: table.to_string(),
+                key_abbr: options.abbr_style.apply(key),
+            }
+        }
+        (Some(_), Some(_), _) => Err(error(
+            "Only one of `@dissector` and `@dissector_table` can be used".into(),
+        ))?,
+        (None, Some(_), None) => Err(error(
+            "`@dissector_table` requires the field to look up, e.g. `@key=<field>`".into(),
+        ))?,
+        (_, None, Some(_)) => Err(error(
+            "`@key` can only be used with `@dissector_table`".into(),
+        ))?,
+    };
+
+    match &field.desc {
+        FieldDesc::Payload { .. } => {}
+        FieldDesc::Body if scope.iter_children(decl).next().is_none() => {}
+        FieldDesc::Body => Err(error(
+            "Bodies with child packets are dissected as the children, and cannot be handed to another dissector".into(),
+        ))?,
+        FieldDesc::Array {
+            width: Some(8),
+            type_id: None,
+            ..
+        } => {}
+        _ => Err(error(
+            "Only payloads and byte arrays can be handed to another dissector".into(),
+        ))?,
+    }
+    Ok(Some(handoff))
+}
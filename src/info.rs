@@ -0,0 +1,136 @@
+//! Templates of the text shown in the Info column for each PDU of a protocol, e.g.
+//! `Request {opcode} for {handle}`, where `{opcode}` and `{handle}` are replaced by the values of
+//! the fields of the target packet with these filter names.
+
+use codespan_reporting::diagnostic::Diagnostic;
+
+use crate::{diagnostics::Diagnostics, utils::lua_string, DeclDissectorInfo, FieldDissectorInfo};
+
+/// A part of an Info column template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    /// The value of the field with this filter name segment.
+    Field(String),
+}
+
+/// A template of the text shown in the Info column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InfoTemplate {
+    parts: Vec<Part>,
+}
+
+impl InfoTemplate {
+    /// Parses `template`, in which fields are written as `{field}` and braces as `{{` and `}}`.
+    pub fn parse(template: &str) -> Result<Self, Diagnostics> {
+        let error = |message: &str| {
+            Diagnostic::error()
+                .with_message(format!("Invalid Info template {template:?}: {message}"))
+        };
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.next_if_eq(&'{').is_some() => text.push('{'),
+                '}' if chars.next_if_eq(&'}').is_some() => text.push('}'),
+                '{' => {
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') if !field.is_empty() => break,
+                            Some('{' | '}') | None => {
+                                Err(error("fields should be written as `{field}`"))?
+                            }
+                            Some(c) => field.push(c),
+                        }
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(field));
+                }
+                '}' => Err(error("unmatched `}`, write it as `}}`"))?,
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Returns the Lua expression of the text for a PDU of `decl`, whose field values are in
+    /// `field_values` under `filter_prefix`. The fields must be scalar or enum fields of `decl`.
+    pub fn to_lua_expr(
+        &self,
+        decl: &DeclDissectorInfo,
+        filter_prefix: &str,
+    ) -> Result<String, Diagnostics> {
+        let DeclDissectorInfo::Sequence { name, fields, .. } = decl else {
+            unreachable!("target packets are sequences")
+        };
+        let mut exprs = vec![];
+        for part in &self.parts {
+            let field_abbr = match part {
+                Part::Text(text) => {
+                    exprs.push(lua_string(text));
+                    continue;
+                }
+                Part::Field(field_abbr) => field_abbr,
+            };
+            let value = format!(r#"field_values["{filter_prefix}.{field_abbr}"]"#);
+            let expr = fields.iter().find_map(|field| match field {
+                FieldDissectorInfo::Scalar { common, .. } if &common.abbr == field_abbr => {
+                    Some(format!("info_value({value})"))
+                }
+                FieldDissectorInfo::Typedef { common, decl, .. } if &common.abbr == field_abbr => {
+                    match decl.as_ref() {
+                        DeclDissectorInfo::Enum { name, .. } => {
+                            Some(format!("info_value({value}, {name}_enum)"))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            });
+            let Some(expr) = expr else {
+                Err(Diagnostic::error()
+                    .with_message(format!(
+                        "The Info template refers to `{field_abbr}`, which is not a scalar or enum field of `{name}`"
+                    ))
+                    .with_notes(vec![
+                        "Fields are named by their display filter name, without the prefix of the protocol".into(),
+                    ]))?
+            };
+            exprs.push(expr);
+        }
+        if exprs.is_empty() {
+            return Ok(lua_string(""));
+        }
+        Ok(exprs.join(" .. "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InfoTemplate, Part};
+
+    #[test]
+    fn test_parse() {
+        let template = InfoTemplate::parse("Request {opcode} {{{handle}}}").unwrap();
+        assert_eq!(
+            template.parts,
+            vec![
+                Part::Text("Request ".into()),
+                Part::Field("opcode".into()),
+                Part::Text(" {".into()),
+                Part::Field("handle".into()),
+                Part::Text("}".into()),
+            ]
+        );
+        assert!(InfoTemplate::parse("{}").is_err());
+        assert!(InfoTemplate::parse("{opcode").is_err());
+        assert!(InfoTemplate::parse("opcode}").is_err());
+    }
+}
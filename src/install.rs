@@ -0,0 +1,423 @@
+//! Installing generated dissectors into the personal plugin folder of Wireshark, where it loads
+//! them from on startup. See
+//! https://www.wireshark.org/docs/wsug_html_chunked/ChPluginFolders.html.
+//!
+//! Wireshark runs every Lua file in the plugin folder, so dissectors installed as modules of a
+//! loader are written to a separate folder, `pdl-dissector/<loader>` next to the plugin folder,
+//! which only the loader adds to the search path of `require`.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use codespan_reporting::diagnostic::Diagnostic;
+
+use crate::{diagnostics::Diagnostics, utils::lua_string, Args};
+
+/// The start of the first line of every Lua file generated by this tool, followed by its version.
+const HEADER_PREFIX: &str = "-- Generated by pdl-dissector ";
+
+/// A Lua file in a plugin folder that was generated by this tool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstalledPlugin {
+    /// The name of the file without the `.lua` extension.
+    pub name: String,
+    pub path: PathBuf,
+    /// The version of this tool that generated the file.
+    pub version: String,
+    /// The dissectors loaded by the file, if it is a loader.
+    pub modules: Vec<InstalledPlugin>,
+}
+
+/// Returns the plugin folder to install into: `plugin_dir` if given, or the personal plugin
+/// folder of Wireshark.
+pub fn resolve_plugin_dir(plugin_dir: Option<PathBuf>) -> Result<PathBuf, Diagnostics> {
+    match plugin_dir {
+        Some(plugin_dir) => Ok(plugin_dir),
+        None => personal_plugin_dir(),
+    }
+}
+
+/// Returns the personal plugin folder of Wireshark, which is `plugins` in `WIRESHARK_CONFIG_DIR`
+/// if it is set, and otherwise `%APPDATA%\Wireshark\plugins` on Windows and
+/// `~/.local/lib/wireshark/plugins` on other systems.
+pub fn personal_plugin_dir() -> Result<PathBuf, Diagnostics> {
+    if let Some(config_dir) = std::env::var_os("WIRESHARK_CONFIG_DIR") {
+        return Ok(PathBuf::from(config_dir).join("plugins"));
+    }
+    let (var, relative_dir) = if cfg!(windows) {
+        ("APPDATA", "Wireshark/plugins")
+    } else {
+        ("HOME", ".local/lib/wireshark/plugins")
+    };
+    let base_dir = std::env::var_os(var).ok_or_else(|| {
+        Diagnostic::error().with_message(format!(
+            "Unable to find the Wireshark plugin folder, because {var} is not set"
+        ))
+    })?;
+    Ok(PathBuf::from(base_dir).join(relative_dir))
+}
+
+/// The name of the file that the dissector for `args` is installed as, which is the file name of
+/// its `output`, or the name of the PDL file with a `.lua` extension.
+pub fn plugin_file_name(args: &Args) -> Result<PathBuf, Diagnostics> {
+    let file_name = match (&args.output, &args.pdl_file) {
+        (Some(output), _) => output
+            .file_name()
+            .map(|file_name| Path::new(file_name).with_extension("lua")),
+        // The extension is appended, so that the dots in e.g. `a.b.pdl` are kept.
+        (None, Some(pdl_file)) if pdl_file.as_os_str() != "-" => pdl_file.file_stem().map(|stem| {
+            let mut file_name = stem.to_owned();
+            file_name.push(".lua");
+            PathBuf::from(file_name)
+        }),
+        _ => None,
+    };
+    file_name.ok_or_else(|| {
+        Diagnostic::error()
+            .with_message(
+                "Unable to name the installed dissector, give its file name with `--output`",
+            )
+            .into()
+    })
+}
+
+/// The name of the file that the dissector for `args` is installed as into the modules folder of a
+/// loader, like [`plugin_file_name`]. Names containing a dot are rejected, because `require` would
+/// look for the part after the dot in a subfolder.
+pub fn module_file_name(args: &Args) -> Result<PathBuf, Diagnostics> {
+    let file_name = plugin_file_name(args)?;
+    if let Some(name) = file_name
+        .file_stem()
+        .filter(|name| name.to_string_lossy().contains('.'))
+    {
+        Err(Diagnostic::error()
+            .with_message(format!(
+                "Module {name:?} cannot be loaded with `require`, because its name contains a dot"
+            ))
+            .with_notes(vec!["Give it another file name with `--output`".into()]))?
+    }
+    Ok(file_name)
+}
+
+/// Checks that `loader` can name a loader, which is a file in the plugin folder.
+pub fn check_loader_name(loader: &str) -> Result<(), Diagnostics> {
+    if loader.is_empty() || loader.contains(['/', '\\', '.']) {
+        Err(Diagnostic::error().with_message(format!(
+            "Loader name {loader:?} should be a file name without extension"
+        )))?
+    }
+    Ok(())
+}
+
+/// The folder that the modules of the loader named `loader` are installed into.
+pub fn modules_dir(plugin_dir: &Path, loader: &str) -> PathBuf {
+    plugin_dir
+        .parent()
+        .unwrap_or(plugin_dir)
+        .join("pdl-dissector")
+        .join(loader)
+}
+
+/// Checks that the file at `path` can be overwritten, because it does not exist or was generated
+/// by this tool.
+pub fn check_overwrite(path: &Path) -> Result<(), Diagnostics> {
+    if path.exists() && generated_version(path).is_none() {
+        Err(Diagnostic::error().with_message(format!(
+            "{} was not generated by pdl-dissector, remove it to install over it",
+            path.display()
+        )))?
+    }
+    Ok(())
+}
+
+/// Writes the loader named `loader` into `plugin_dir`, which `require`s all dissectors installed
+/// in its modules folder. Returns the path of the loader.
+pub fn write_loader(plugin_dir: &Path, loader: &str) -> Result<PathBuf, Diagnostics> {
+    check_loader_name(loader)?;
+    let modules_dir = modules_dir(plugin_dir, loader);
+    let modules = generated_files(&modules_dir)?;
+    if let Some(module) = modules.iter().find(|module| module.name.contains('.')) {
+        // Only modules installed by hand, or by older versions, can be named like this.
+        Err(Diagnostic::error().with_message(format!(
+            "Module {:?} cannot be loaded with `require`, because its name contains a dot",
+            module.name
+        )))?
+    }
+    let path = plugin_dir.join(loader).with_extension("lua");
+    check_overwrite(&path)?;
+    let mut contents = vec![];
+    writeln!(
+        contents,
+        "{HEADER_PREFIX}{}. Do not edit, regenerate it instead.",
+        env!("CARGO_PKG_VERSION")
+    )?;
+    writeln!(
+        contents,
+        "-- Loader of the dissectors in {}",
+        modules_dir.display()
+    )?;
+    let search_path = format!("{};", modules_dir.join("?.lua").display());
+    writeln!(
+        contents,
+        "package.path = {} .. package.path",
+        lua_string(&search_path)
+    )?;
+    for module in &modules {
+        writeln!(contents, "require({})", lua_string(&module.name))?;
+    }
+    std::fs::create_dir_all(plugin_dir)
+        .and_then(|()| std::fs::write(&path, contents))
+        .map_err(|err| {
+            Diagnostic::error().with_message(format!("Unable to write {}: {err}", path.display()))
+        })?;
+    Ok(path)
+}
+
+/// Lists the files generated by this tool in `plugin_dir`, with the modules of loaders.
+pub fn installed_plugins(plugin_dir: &Path) -> Result<Vec<InstalledPlugin>, Diagnostics> {
+    let mut plugins = generated_files(plugin_dir)?;
+    for plugin in &mut plugins {
+        plugin.modules = generated_files(&modules_dir(plugin_dir, &plugin.name))?;
+    }
+    Ok(plugins)
+}
+
+/// Removes the plugin named `name` from `plugin_dir`, and the modules it loads if it is a loader.
+/// Only files generated by this tool are removed. Returns the paths of the removed files.
+pub fn uninstall(plugin_dir: &Path, name: &str) -> Result<Vec<PathBuf>, Diagnostics> {
+    let Some(plugin) = installed_plugins(plugin_dir)?
+        .into_iter()
+        .find(|plugin| plugin.name == name)
+    else {
+        Err(Diagnostic::error().with_message(format!(
+            "Unable to find a dissector named {name:?} generated by pdl-dissector in {}",
+            plugin_dir.display()
+        )))?
+    };
+    let remove_error = |path: &Path| {
+        let path = path.display().to_string();
+        move |err: std::io::Error| {
+            Diagnostic::error().with_message(format!("Unable to remove {path}: {err}"))
+        }
+    };
+    let mut removed = vec![];
+    for module in plugin.modules {
+        std::fs::remove_file(&module.path).map_err(remove_error(&module.path))?;
+        removed.push(module.path);
+    }
+    let modules_dir = modules_dir(plugin_dir, name);
+    // Only succeeds if nothing else was put in the folder.
+    let _ = std::fs::remove_dir(&modules_dir);
+    std::fs::remove_file(&plugin.path).map_err(remove_error(&plugin.path))?;
+    removed.push(plugin.path);
+    Ok(removed)
+}
+
+/// The Lua files generated by this tool in `dir`, sorted by name. A missing folder has none.
+fn generated_files(dir: &Path) -> Result<Vec<InstalledPlugin>, Diagnostics> {
+    let entries =
+        match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => Err(Diagnostic::error()
+                .with_message(format!("Unable to read {}: {err}", dir.display())))?,
+        };
+    let mut plugins = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "lua") {
+            if let (Some(name), Some(version)) = (path.file_stem(), generated_version(&path)) {
+                plugins.push(InstalledPlugin {
+                    name: name.to_string_lossy().into_owned(),
+                    path,
+                    version,
+                    modules: vec![],
+                });
+            }
+        }
+    }
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(plugins)
+}
+
+/// The version of this tool that generated the file at `path`, read from its header, or `None`
+/// if it was not generated by this tool.
+fn generated_version(path: &Path) -> Option<String> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    let version = contents.lines().next()?.strip_prefix(HEADER_PREFIX)?;
+    Some(
+        version
+            .split_once(". ")
+            .map_or(version, |(version, _)| version)
+            .to_owned(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use clap::{CommandFactory as _, FromArgMatches as _};
+    use pdl_compiler::ast::SourceDatabase;
+
+    use crate::{fakes::wireshark_lua, run_cli, run_install, tests::run_with_args, Args, Cli};
+
+    #[test]
+    fn test_install() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pdl_file = dir.path().join("packets.pdl");
+        std::fs::write(
+            &pdl_file,
+            "little_endian_packets\npacket Message {\n  value: 16,\n}\n",
+        )?;
+        let args = || Args {
+            pdl_file: Some(pdl_file.clone()),
+            target_packets: vec!["Message".into()],
+            ..Default::default()
+        };
+        let plugin_dir = dir.path().join("wireshark/plugins");
+        let (path, _) = run_install(args(), &plugin_dir, &mut SourceDatabase::new())?;
+        assert_eq!(path, plugin_dir.join("packets.lua"));
+        assert_eq!(std::fs::read(&path)?, run_with_args(args()));
+
+        // The output file only names the installed dissector.
+        let (path, _) = run_install(
+            Args {
+                output: Some(PathBuf::from("elsewhere/message.lua")),
+                ..args()
+            },
+            &plugin_dir,
+            &mut SourceDatabase::new(),
+        )?;
+        assert_eq!(path, plugin_dir.join("message.lua"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_install_loader() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        for (name, packet) in [("first", "First"), ("second", "Second")] {
+            std::fs::write(
+                dir.path().join(format!("{name}.pdl")),
+                format!("little_endian_packets\npacket {packet} {{\n  value: 16,\n}}\n"),
+            )?;
+        }
+        let plugin_dir = dir.path().join("wireshark/plugins");
+        let run_command_line = |command_line: &[&str]| -> anyhow::Result<String> {
+            let matches = Cli::command().try_get_matches_from(
+                ["pdl-dissector"].iter().chain(command_line).map(|arg| {
+                    arg.replace("DIR", dir.path().to_str().unwrap())
+                        .replace("PLUGINS", plugin_dir.to_str().unwrap())
+                }),
+            )?;
+            let cli = Cli::from_arg_matches(&matches)?;
+            let mut output = vec![];
+            run_cli(cli, &matches, &mut SourceDatabase::new(), &mut output)?;
+            Ok(String::from_utf8(output)?)
+        };
+        for (name, packet) in [("first", "First"), ("second", "Second")] {
+            run_command_line(&[
+                "install",
+                &format!("DIR/{name}.pdl"),
+                packet,
+                "--plugin-dir",
+                "PLUGINS",
+                "--loader",
+                "stack",
+            ])?;
+        }
+        run_command_line(&[
+            "install",
+            "DIR/first.pdl",
+            "First",
+            "--plugin-dir",
+            "PLUGINS",
+        ])?;
+
+        // Only the loader is in the plugin folder, and it loads both modules.
+        let modules_dir = dir.path().join("wireshark/pdl-dissector/stack");
+        assert!(modules_dir.join("first.lua").exists());
+        let loader = std::fs::read_to_string(plugin_dir.join("stack.lua"))?;
+        assert!(loader.ends_with("require(\"first\")\nrequire(\"second\")\n"));
+        let lua = wireshark_lua()?;
+        lua.load(&loader).exec()?;
+        lua.load("assert(First_protocol and Second_protocol)")
+            .exec()?;
+
+        let version = env!("CARGO_PKG_VERSION");
+        let installed = run_command_line(&["installed", "--plugin-dir", "PLUGINS"])?;
+        assert_eq!(
+            installed,
+            format!(
+                "first {version} {}\nstack {version} {}\n    first {version} {}\n    second {version} {}\n",
+                plugin_dir.join("first.lua").display(),
+                plugin_dir.join("stack.lua").display(),
+                modules_dir.join("first.lua").display(),
+                modules_dir.join("second.lua").display(),
+            )
+        );
+
+        run_command_line(&["uninstall", "stack", "--plugin-dir", "PLUGINS"])?;
+        assert!(!modules_dir.exists());
+        assert!(!plugin_dir.join("stack.lua").exists());
+        assert!(plugin_dir.join("first.lua").exists());
+
+        // Files that were not generated are neither overwritten nor removed.
+        std::fs::write(plugin_dir.join("mine.lua"), "-- Written by hand\n")?;
+        let error = run_command_line(&[
+            "install",
+            "DIR/first.pdl",
+            "First",
+            "-o",
+            "mine.lua",
+            "--plugin-dir",
+            "PLUGINS",
+        ])
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("was not generated by pdl-dissector"));
+        assert!(run_command_line(&["uninstall", "mine", "--plugin-dir", "PLUGINS"]).is_err());
+        assert_eq!(
+            std::fs::read_to_string(plugin_dir.join("mine.lua"))?,
+            "-- Written by hand\n"
+        );
+
+        // Names that `require` can't load are rejected before anything is written.
+        std::fs::copy(dir.path().join("first.pdl"), dir.path().join("a.b.pdl"))?;
+        let error = run_command_line(&[
+            "install",
+            "DIR/a.b.pdl",
+            "First",
+            "--plugin-dir",
+            "PLUGINS",
+            "--loader",
+            "dotted",
+        ])
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("because its name contains a dot"));
+        let error = run_command_line(&[
+            "install",
+            "DIR/first.pdl",
+            "First",
+            "--plugin-dir",
+            "PLUGINS",
+            "--loader",
+            "dotted.lua",
+        ])
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("should be a file name without extension"));
+        assert!(!dir.path().join("wireshark/pdl-dissector/dotted").exists());
+        assert!(!dir
+            .path()
+            .join("wireshark/pdl-dissector/dotted.lua")
+            .exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,590 @@
+//! A backend-neutral intermediate representation (IR) of what the dissector generator understood
+//! from a PDL file, built with [`Generator::ir`](crate::Generator::ir). It has the declarations
+//! with their fields in the order they are dissected, the lengths of the fields as sums of
+//! constants and the values of other fields, the checks on their values, and the constraints that
+//! select child packets. It can be serialized with serde, e.g. to generate dissectors for other
+//! tools, or to inspect it with `pdl-dissector ir`.
+//!
+//! Declarations refer to each other by name. Fields are named by their filter name segment in the
+//! abbreviation style of the generator, e.g. `_payload__size` for the size field of a payload,
+//! and refer to other fields of the same declaration or its ancestors by that name.
+
+use pdl_compiler::ast::{EndiannessValue, Tag};
+use serde::{Deserialize, Serialize};
+
+pub use crate::{
+    display::{DisplayType, FieldDisplay, IntegerBase},
+    handoff::Handoff,
+    Framing,
+};
+use crate::{
+    len_info::RuntimeLenInfo, ArrayFieldDissectorInfo, ChildDissectorInfo,
+    CommonFieldDissectorInfo, ConstraintDissectorInfo, DeclDissectorInfo, FieldDissectorInfo,
+};
+
+/// The version of the representation, which is increased when its serialized form changes in a
+/// way that consumers need to handle, e.g. when a field is renamed or a variant is added.
+pub const VERSION: u32 = 1;
+
+/// The declarations of a PDL file, and the protocols generated for its target packets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct File {
+    /// The [`VERSION`] of the representation the file was built with.
+    pub version: u32,
+    pub protocols: Vec<Protocol>,
+    /// The declarations in the order they are declared.
+    pub declarations: Vec<Decl>,
+}
+
+/// A protocol generated for a target packet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Protocol {
+    /// The name of the target packet.
+    pub packet: String,
+    /// The name shown in the "Protocol" column.
+    pub short_name: String,
+    /// The name shown in the protocol tree.
+    pub full_name: String,
+    /// The prefix of the filter names of all fields of the protocol.
+    pub filter_prefix: String,
+    pub framing: Framing,
+    /// The TCP ports the protocol is registered on.
+    pub tcp_ports: Vec<u16>,
+    /// The UDP ports the protocol is registered on.
+    pub udp_ports: Vec<u16>,
+    /// The template of the text shown in the Info column, as given with `--info`.
+    pub info: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Decl {
+    /// A packet, struct, group or custom field, which is dissected field by field.
+    Sequence {
+        name: String,
+        /// The filter name segment of the declaration when it is dissected as a child.
+        abbr: String,
+        fields: Vec<Field>,
+        /// The declarations that derive from this one.
+        children: Vec<String>,
+        /// The constraints on the fields of the parent declaration that select this one.
+        constraints: Vec<Constraint>,
+    },
+    Enum {
+        name: String,
+        width: usize,
+        tags: Vec<EnumTag>,
+    },
+    Checksum {
+        name: String,
+        width: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum EnumTag {
+    Value(TagValue),
+    /// The inclusive range of values from `start` to `end`, with names for some of its values.
+    Range {
+        name: String,
+        start: usize,
+        end: usize,
+        values: Vec<TagValue>,
+    },
+    /// The name of all values without a tag.
+    Other {
+        name: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagValue {
+    pub name: String,
+    pub value: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Field {
+    /// The name shown in the protocol tree, e.g. `Size(Payload)`.
+    pub display_name: String,
+    /// The filter name segment of the field, e.g. `_payload__size`.
+    pub abbr: String,
+    /// The offset of the first bit of the field in the byte it starts in.
+    pub bit_offset: usize,
+    pub endianness: Endianness,
+    /// The comment documenting the field, without its annotations.
+    pub description: Option<String>,
+    pub kind: FieldKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum FieldKind {
+    /// A scalar, including size, count, fixed, reserved and flag fields.
+    Scalar {
+        /// The width in bits, or `None` if it is only known when dissecting.
+        width: Option<usize>,
+        length: Length,
+        display: FieldDisplay,
+        /// A check on the value, which is reported when it fails.
+        validation: Option<Validation>,
+        /// The flag that this optional field is present for.
+        condition: Option<Condition>,
+    },
+    /// A payload or body, which is dissected as one of the children of the declaration, handed
+    /// to another dissector, or shown as bytes.
+    Payload {
+        length: Length,
+        children: Vec<Child>,
+        handoff: Option<Handoff>,
+    },
+    /// A field of the declaration `type_name`, e.g. a struct or enum.
+    Typedef {
+        type_name: String,
+        /// The flag that this optional field is present for.
+        condition: Option<Condition>,
+    },
+    /// An array of items of the declaration `type_name`.
+    TypedefArray { type_name: String, array: Array },
+    /// An array of scalars.
+    ScalarArray {
+        item_width: usize,
+        array: Array,
+        display: FieldDisplay,
+        handoff: Option<Handoff>,
+    },
+}
+
+/// The length of a field, in bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Length {
+    /// `bits` plus the values in bytes of the `fields`, e.g. a `_size_` field. The length is
+    /// unknown if one of the fields was not dissected.
+    Sum { bits: usize, fields: Vec<String> },
+    /// The rest of the buffer.
+    Unbounded,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Array {
+    pub length: ArrayLength,
+    /// The size in bytes that the array is padded to with a `_padding_` field.
+    pub padded_size: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ArrayLength {
+    /// A fixed number of items.
+    Count(usize),
+    /// The number of items given by the value of a `_count_` field.
+    CountField(String),
+    /// The size in bytes given by the value of a `_size_` field, plus `modifier`.
+    SizeField { field: String, modifier: usize },
+    /// Items until the end of the buffer.
+    Unbounded,
+}
+
+/// A check on the value of a scalar field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Validation {
+    /// The value of a fixed field.
+    Equals(usize),
+    /// The tag of a fixed enum field.
+    EnumTag { enum_id: String, tag_id: String },
+}
+
+/// The presence of an optional field, which is present when its flag has `value`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Condition {
+    pub flag: String,
+    pub value: usize,
+}
+
+/// A child declaration that a payload or body may be dissected as.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Child {
+    pub name: String,
+    /// The constraints on the fields of the parent that select this child.
+    pub constraints: Vec<Constraint>,
+}
+
+/// A constraint on the value of a field of a parent declaration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Constraint {
+    Value {
+        field: String,
+        value: usize,
+    },
+    EnumTag {
+        field: String,
+        enum_id: String,
+        tag_id: String,
+    },
+}
+
+impl From<&DeclDissectorInfo> for Decl {
+    fn from(info: &DeclDissectorInfo) -> Self {
+        match info {
+            DeclDissectorInfo::Sequence {
+                name,
+                abbr,
+                fields,
+                children,
+                constraints,
+            } => Decl::Sequence {
+                name: name.clone(),
+                abbr: abbr.clone(),
+                fields: fields.iter().map(Field::from).collect(),
+                children: children
+                    .iter()
+                    .map(|child| child.name().to_owned())
+                    .collect(),
+                constraints: constraints.iter().map(Constraint::from).collect(),
+            },
+            DeclDissectorInfo::Enum { name, values, len } => Decl::Enum {
+                name: name.clone(),
+                width: len.0,
+                tags: values.iter().map(EnumTag::from).collect(),
+            },
+            DeclDissectorInfo::Checksum { name, len } => Decl::Checksum {
+                name: name.clone(),
+                width: len.0,
+            },
+        }
+    }
+}
+
+impl From<&Tag> for EnumTag {
+    fn from(tag: &Tag) -> Self {
+        let tag_value = |tag: &pdl_compiler::ast::TagValue| TagValue {
+            name: tag.id.clone(),
+            value: tag.value,
+        };
+        match tag {
+            Tag::Value(tag) => EnumTag::Value(tag_value(tag)),
+            Tag::Range(tag) => EnumTag::Range {
+                name: tag.id.clone(),
+                start: *tag.range.start(),
+                end: *tag.range.end(),
+                values: tag.tags.iter().map(tag_value).collect(),
+            },
+            Tag::Other(tag) => EnumTag::Other {
+                name: tag.id.clone(),
+            },
+        }
+    }
+}
+
+impl From<&FieldDissectorInfo> for Field {
+    fn from(info: &FieldDissectorInfo) -> Self {
+        let CommonFieldDissectorInfo {
+            display_name,
+            abbr,
+            bit_offset,
+            endian,
+            comments,
+        } = info.common();
+        let kind = match info {
+            FieldDissectorInfo::Scalar {
+                ftype,
+                len,
+                display,
+                validation,
+                optional_field,
+                ..
+            } => FieldKind::Scalar {
+                width: ftype.0.map(|width| width.0),
+                length: len.into(),
+                display: display.clone(),
+                validation: validation.clone(),
+                condition: optional_field.as_ref().map(Condition::from),
+            },
+            FieldDissectorInfo::Payload {
+                len,
+                children,
+                handoff,
+                ..
+            } => FieldKind::Payload {
+                length: len.into(),
+                children: children.iter().map(Child::from).collect(),
+                handoff: handoff.clone(),
+            },
+            FieldDissectorInfo::Typedef {
+                decl,
+                optional_field,
+                ..
+            } => FieldKind::Typedef {
+                type_name: decl.name().to_owned(),
+                condition: optional_field.as_ref().map(Condition::from),
+            },
+            FieldDissectorInfo::TypedefArray {
+                decl, array_info, ..
+            } => FieldKind::TypedefArray {
+                type_name: decl.name().to_owned(),
+                array: Array::new(array_info, abbr),
+            },
+            FieldDissectorInfo::ScalarArray {
+                item_len,
+                array_info,
+                display,
+                handoff,
+                ..
+            } => FieldKind::ScalarArray {
+                item_width: item_len.0,
+                array: Array::new(array_info, abbr),
+                display: display.clone(),
+                handoff: handoff.clone(),
+            },
+        };
+        Field {
+            display_name: display_name.clone(),
+            abbr: abbr.clone(),
+            bit_offset: bit_offset.0,
+            endianness: match endian {
+                EndiannessValue::LittleEndian => Endianness::Little,
+                EndiannessValue::BigEndian => Endianness::Big,
+            },
+            description: comments.clone(),
+            kind,
+        }
+    }
+}
+
+impl From<&RuntimeLenInfo> for Length {
+    fn from(len: &RuntimeLenInfo) -> Self {
+        match len {
+            RuntimeLenInfo::Bounded {
+                referenced_fields,
+                constant_factor,
+            } => Length::Sum {
+                bits: constant_factor.0,
+                fields: referenced_fields.clone(),
+            },
+            RuntimeLenInfo::Unbounded => Length::Unbounded,
+        }
+    }
+}
+
+impl Array {
+    /// The array `abbr`, whose `_size_` or `_count_` field is named after it.
+    fn new(info: &ArrayFieldDissectorInfo, abbr: &str) -> Self {
+        // In the order the generated dissectors use them.
+        let length = if let Some(count) = info.count {
+            ArrayLength::Count(count)
+        } else if info.has_count_field {
+            ArrayLength::CountField(format!("{abbr}_count"))
+        } else if info.has_size_field {
+            ArrayLength::SizeField {
+                field: format!("{abbr}_size"),
+                modifier: info.size_modifier.unwrap_or_default(),
+            }
+        } else {
+            ArrayLength::Unbounded
+        };
+        Array {
+            length,
+            padded_size: info.pad_to_size,
+        }
+    }
+}
+
+impl From<&(String, usize)> for Condition {
+    fn from((flag, value): &(String, usize)) -> Self {
+        Condition {
+            flag: flag.clone(),
+            value: *value,
+        }
+    }
+}
+
+impl From<&ChildDissectorInfo> for Child {
+    fn from(info: &ChildDissectorInfo) -> Self {
+        Child {
+            name: info.name.clone(),
+            constraints: info.constraints.iter().map(Constraint::from).collect(),
+        }
+    }
+}
+
+impl From<&ConstraintDissectorInfo> for Constraint {
+    fn from(info: &ConstraintDissectorInfo) -> Self {
+        match info {
+            ConstraintDissectorInfo::ValueMatch { field, value } => Constraint::Value {
+                field: field.clone(),
+                value: *value,
+            },
+            ConstraintDissectorInfo::EnumMatch {
+                field,
+                enum_type,
+                enum_value,
+            } => Constraint::EnumTag {
+                field: field.clone(),
+                enum_id: enum_type.clone(),
+                tag_id: enum_value.clone(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pdl_compiler::ast::SourceDatabase;
+
+    use super::{
+        Array, ArrayLength, Condition, Constraint, Decl, EnumTag, Field, FieldKind, File, Length,
+        Protocol, Validation, VERSION,
+    };
+    use crate::{Framing, Generator};
+
+    #[test]
+    fn test_ir() -> anyhow::Result<()> {
+        let source = indoc! {r#"
+            little_endian_packets
+            enum Kind : 8 {
+                DATA = 1,
+                OTHER = 2..3,
+            }
+            packet Frame {
+                _fixed_ = 0x7e : 8,
+                kind: Kind,
+                _size_(_body_): 8,
+                _body_,
+            }
+            packet Data : Frame (kind = DATA) {
+                _size_(items): 8,
+                items: 16[+2],
+                has_extra: 1,
+                _reserved_: 7,
+                extra: 8 if has_extra = 1,
+            }
+        "#};
+        let file = Generator::from_source("frame.pdl", source)
+            .target("Frame")
+            .protocol_name("FRAME")
+            .tcp_port(8000)
+            .info("{kind}")
+            .ir(&mut SourceDatabase::new())?;
+        assert_eq!(file.version, VERSION);
+        assert_eq!(
+            file.protocols,
+            vec![Protocol {
+                packet: "Frame".into(),
+                short_name: "FRAME".into(),
+                full_name: "FRAME".into(),
+                filter_prefix: "frame".into(),
+                framing: Framing::Single,
+                tcp_ports: vec![8000],
+                udp_ports: vec![],
+                info: Some("{kind}".into()),
+            }]
+        );
+        let [Decl::Enum { name, width, tags }, Decl::Sequence {
+            fields: frame_fields,
+            children,
+            ..
+        }, Decl::Sequence {
+            fields: data_fields,
+            constraints,
+            ..
+        }] = file.declarations.as_slice()
+        else {
+            panic!("Unexpected declarations: {:?}", file.declarations)
+        };
+        assert_eq!((name.as_str(), *width), ("Kind", 8));
+        assert_eq!(
+            tags[1],
+            EnumTag::Range {
+                name: "OTHER".into(),
+                start: 2,
+                end: 3,
+                values: vec![],
+            }
+        );
+        assert_eq!(children, &["Data"]);
+        assert_eq!(
+            constraints,
+            &[Constraint::EnumTag {
+                field: "kind".into(),
+                enum_id: "Kind".into(),
+                tag_id: "DATA".into(),
+            }]
+        );
+        let kinds = |fields: &[Field]| {
+            fields
+                .iter()
+                .map(|field| (field.abbr.clone(), field.kind.clone()))
+                .collect::<Vec<_>>()
+        };
+        let frame_kinds = kinds(frame_fields);
+        assert!(matches!(
+            &frame_kinds[0].1,
+            FieldKind::Scalar {
+                validation: Some(Validation::Equals(0x7e)),
+                ..
+            }
+        ));
+        assert!(matches!(
+            &frame_kinds[1].1,
+            FieldKind::Typedef { type_name, condition: None } if type_name == "Kind"
+        ));
+        let FieldKind::Payload {
+            length, children, ..
+        } = &frame_kinds[3].1
+        else {
+            panic!("Unexpected payload: {:?}", frame_kinds[3])
+        };
+        assert_eq!(
+            length,
+            &Length::Sum {
+                bits: 0,
+                fields: vec!["_body__size".into()],
+            }
+        );
+        assert_eq!(children[0].name, "Data");
+        let data_kinds = kinds(data_fields);
+        assert!(matches!(
+            &data_kinds[1].1,
+            FieldKind::ScalarArray {
+                item_width: 16,
+                array: Array {
+                    length: ArrayLength::SizeField { field, modifier: 2 },
+                    padded_size: None,
+                },
+                ..
+            } if field == "items_size"
+        ));
+        assert_eq!(data_kinds[4].0, "extra");
+        assert!(matches!(
+            &data_kinds[4].1,
+            FieldKind::Scalar {
+                condition: Some(Condition { flag, value: 1 }),
+                ..
+            } if flag == "has_extra"
+        ));
+
+        let json = serde_json::to_string(&file)?;
+        assert_eq!(serde_json::from_str::<File>(&json)?, file);
+        Ok(())
+    }
+}
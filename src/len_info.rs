@@ -63,8 +63,7 @@ impl RuntimeLenInfo {
                     constant_factor: other_constant_factor,
                 },
             ) => RuntimeLenInfo::Bounded {
-                referenced_fields: [referenced_fields.as_slice(), &other_referenced_fields]
-                    .concat(),
+                referenced_fields: [referenced_fields.as_slice(), other_referenced_fields].concat(),
                 constant_factor: BitLen(constant_factor.0 + other_constant_factor.0),
             },
             _ => RuntimeLenInfo::Unbounded,
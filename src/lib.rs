@@ -1,9 +1,22 @@
+pub mod build;
+pub mod check;
 mod comments;
+pub mod config;
 pub mod diagnostics;
+mod display;
+mod experts;
 #[cfg(test)]
 mod fakes;
+mod framing;
+mod generator;
+mod handoff;
 mod indent_write;
+mod info;
+pub mod install;
+pub mod ir;
 mod len_info;
+mod list;
+mod naming;
 pub mod pdml;
 mod utils;
 
@@ -11,24 +24,35 @@ use ::indent_write::io::IndentWriter;
 use codespan_reporting::diagnostic::Diagnostic;
 use comments::ToLuaExpr;
 use diagnostics::Diagnostics;
+use display::{field_display, FieldDisplay};
+use experts::{write_experts_table, Expert};
+pub use framing::Framing;
+use framing::{write_multiple_dissector, write_tcp_dissector, PduLen};
+pub use generator::Generator;
+pub use handoff::Binding;
+use handoff::{field_handoff, Handoff};
 use indent_write::IoWriteExt;
 use indoc::writedoc;
+use ir::Validation;
 use len_info::{FType, RuntimeLenInfo};
 use log::debug;
+pub use naming::AbbrStyle;
+use naming::ProtocolNames;
 use pdl_compiler::{
     analyzer::{self, Scope},
     ast::{
-        Annotation, Constraint, Decl, DeclDesc, EndiannessValue, Field, FieldDesc, SourceDatabase,
-        Tag, TagOther, TagRange, TagValue,
+        Constraint, Decl, DeclDesc, EndiannessValue, Field, FieldDesc, FileId, SourceDatabase, Tag,
+        TagOther, TagRange, TagValue,
     },
 };
-use std::{collections::HashMap, io::Write, path::PathBuf};
-use utils::{buffer_value_lua_function, lua_if_then_else};
-
-use crate::{
-    comments::{find_comments_on_same_line, unwrap_comment},
-    len_info::BitLen,
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
 };
+use utils::{buffer_value_lua_function, lua_if_then_else, lua_string};
+
+use crate::{comments::field_description, len_info::BitLen};
 
 #[derive(Clone, Debug)]
 struct FieldContext<'a> {
@@ -36,27 +60,72 @@ struct FieldContext<'a> {
     num_reserved: usize,
     optional_decl: HashMap<String, (String, usize)>,
     scope: &'a Scope<'a, analyzer::ast::Annotation>,
+    options: &'a DissectorOptions,
+    /// Problems found in the annotations of the fields.
+    diagnostics: Vec<Diagnostic<FileId>>,
 }
 
 impl<'a> FieldContext<'a> {
-    pub fn new(scope: &'a Scope<'a, analyzer::ast::Annotation>) -> Self {
+    pub fn new(
+        scope: &'a Scope<'a, analyzer::ast::Annotation>,
+        options: &'a DissectorOptions,
+    ) -> Self {
         Self {
             num_fixed: 0,
             num_reserved: 0,
             optional_decl: HashMap::default(),
             scope,
+            options,
+            diagnostics: vec![],
         }
     }
+
+    /// Returns how `field` is displayed, recording any problem with its annotations.
+    fn display(
+        &mut self,
+        field: &Field<analyzer::ast::Annotation>,
+        decl: &Decl<analyzer::ast::Annotation>,
+        bit_offset: BitLen,
+    ) -> FieldDisplay {
+        field_display(field, decl, bit_offset, self.options, self.scope.file).unwrap_or_else(
+            |diagnostic| {
+                self.diagnostics.push(diagnostic);
+                FieldDisplay::default()
+            },
+        )
+    }
+
+    /// Returns the dissector that `field` is handed to, recording any problem with its
+    /// annotations.
+    fn handoff(
+        &mut self,
+        field: &Field<analyzer::ast::Annotation>,
+        decl: &Decl<analyzer::ast::Annotation>,
+    ) -> Option<Handoff> {
+        field_handoff(field, decl, self.scope, self.options, self.scope.file).unwrap_or_else(
+            |diagnostic| {
+                self.diagnostics.push(diagnostic);
+                None
+            },
+        )
+    }
+
+    /// Returns the filter name segment for the given PDL identifier.
+    fn abbr(&self, id: &str) -> String {
+        self.options.abbr_style.apply(id)
+    }
 }
 
 trait DeclExt {
-    fn to_dissector_info(&self, scope: &Scope) -> DeclDissectorInfo;
+    fn to_dissector_info(&self, scope: &Scope, options: &DissectorOptions) -> DeclDissectorInfo;
 }
 
 #[derive(Debug, Clone)]
 pub enum DeclDissectorInfo {
     Sequence {
         name: String,
+        /// Filter name segment of this declaration, used when it is dissected as a child.
+        abbr: String,
         fields: Vec<FieldDissectorInfo>,
         children: Vec<DeclDissectorInfo>,
         constraints: Vec<ConstraintDissectorInfo>,
@@ -83,6 +152,7 @@ impl DeclDissectorInfo {
                     fields,
                     children,
                     constraints,
+                    ..
                 } => format!(
                     "Sequence: {name} ({} fields, {} children, {} constraints)",
                     fields.len(),
@@ -101,7 +171,7 @@ impl DeclDissectorInfo {
                 name,
                 fields,
                 children,
-                constraints: _,
+                ..
             } => {
                 writeln!(writer, r#"function {name}_protocol_fields(fields, path)"#)?;
                 for field in fields {
@@ -109,9 +179,10 @@ impl DeclDissectorInfo {
                 }
                 for child in children {
                     let child_name = child.name();
+                    let child_abbr = child.abbr();
                     writeln!(
                         writer.indent(),
-                        r#"{child_name}_protocol_fields(fields, path .. ".{child_name}")"#
+                        r#"{child_name}_protocol_fields(fields, path .. ".{child_abbr}")"#
                     )?;
                 }
                 writeln!(writer, r#"end"#)?;
@@ -157,30 +228,159 @@ impl DeclDissectorInfo {
         Ok(())
     }
 
-    pub fn write_main_dissector(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+    /// Writes the protocol dissector of this target packet. `info_expr` is the Lua expression of
+    /// the text shown in the Info column for each PDU, computed from its `field_values`.
+    pub fn write_main_dissector(
+        &self,
+        writer: &mut impl std::io::Write,
+        names: &ProtocolNames,
+        framing: Framing,
+        info_expr: Option<&str>,
+    ) -> std::io::Result<()> {
+        let ProtocolNames {
+            short_name,
+            filter_prefix,
+            ..
+        } = names;
+        let full_name = names.escaped_full_name();
         match self {
             DeclDissectorInfo::Sequence { name, .. } => {
+                write_experts_table(writer, &format!("{name}_protocol_experts"), filter_prefix)?;
+                // Unless each buffer holds a single PDU, the protocol dissector splits the buffer
+                // into PDUs, which are dissected by `{name}_protocol_dissect_pdu`.
+                let dissect_pdu_fn = match framing {
+                    Framing::Single => format!("{name}_protocol.dissector"),
+                    Framing::Tcp | Framing::Multiple => format!("{name}_protocol_dissect_pdu"),
+                };
+                // The Info column text of the PDUs after the first one of a buffer is appended.
+                let append_info = info_expr.is_some() && framing != Framing::Single;
                 writedoc!(
                     writer,
                     r#"
                     {name}_protocol_fields_table = {{}}
-                    function {name}_protocol.dissector(buffer, pinfo, tree)
-                        pinfo.cols.protocol = "{name}"
-                        local subtree = tree:add({name}_protocol, buffer(), "{name}")
-                        local i = {name}_dissect(buffer, pinfo, subtree, {name}_protocol_fields_table, "{name}")
-                        if buffer(i):len() > 0 then
-                            local remaining_bytes = buffer:len() - i
-                            if math.floor(remaining_bytes) == remaining_bytes then
-                                subtree:add_expert_info(PI_MALFORMED, PI_WARN, "Error: " .. remaining_bytes .. " undissected bytes remaining")
-                            else
-                                subtree:add_expert_info(PI_MALFORMED, PI_WARN, "Error: " .. (remaining_bytes * 8) .. " undissected bits remaining")
+                    function {dissect_pdu_fn}(buffer, pinfo, tree{append_info_param})
+                        pinfo.cols.protocol = "{short_name}"
+                        local subtree = tree:add({name}_protocol, buffer(), "{full_name}")
+                    "#,
+                    append_info_param = if append_info { ", append_info" } else { "" },
+                )?;
+                // The values of the fields are only needed for the Info column.
+                let field_values_arg = if info_expr.is_some() {
+                    writeln!(writer.indent(), "local field_values = {{}}")?;
+                    ", field_values"
+                } else {
+                    ""
+                };
+                if self.has_child_packets() {
+                    writedoc!(
+                        writer.indent(),
+                        r#"
+                        local ok, i, packet_chain = pcall({name}_dissect, buffer, pinfo, subtree, {name}_protocol_fields_table, {name}_protocol_experts, "{filter_prefix}"{field_values_arg})
+                        if not ok then
+                            i = report_truncated(subtree, buffer, "{short_name}", i)
+                        end
+                        if packet_chain ~= nil then
+                            subtree:add({name}_protocol_fields_table["{filter_prefix}._packet_chain_"].field, packet_chain):set_generated()
+                        end
+                        "#,
+                    )?;
+                } else {
+                    writedoc!(
+                        writer.indent(),
+                        r#"
+                        local ok, i = pcall({name}_dissect, buffer, pinfo, subtree, {name}_protocol_fields_table, {name}_protocol_experts, "{filter_prefix}"{field_values_arg})
+                        if not ok then
+                            i = report_truncated(subtree, buffer, "{short_name}", i)
+                        end
+                        "#,
+                    )?;
+                }
+                match info_expr {
+                    Some(info_expr) if append_info => writedoc!(
+                        writer.indent(),
+                        r#"
+                        if append_info then
+                            pinfo.cols.info:append(", " .. {info_expr})
+                        else
+                            pinfo.cols.info:set({info_expr})
+                        end
+                        "#,
+                    )?,
+                    Some(info_expr) => {
+                        writeln!(writer.indent(), "pinfo.cols.info:set({info_expr})")?
+                    }
+                    None => {}
+                }
+                let trailing_bytes =
+                    format!("{name}_protocol_experts.{}", Expert::TrailingBytes.key());
+                if framing == Framing::Multiple {
+                    // The bytes after the PDU are dissected as the next PDU
+                    writedoc!(
+                        writer,
+                        r#"
+                            subtree:set_len(math.ceil(i))
+                            return math.ceil(i)
+                        end
+                        "#,
+                    )?;
+                } else {
+                    writedoc!(
+                        writer,
+                        r#"
+                            if buffer(i):len() > 0 then
+                                local remaining_bytes = buffer:len() - i
+                                if math.floor(remaining_bytes) == remaining_bytes then
+                                    subtree:add_proto_expert_info({trailing_bytes}, "Error: " .. remaining_bytes .. " undissected bytes remaining")
+                                else
+                                    subtree:add_proto_expert_info({trailing_bytes}, "Error: " .. (remaining_bytes * 8) .. " undissected bits remaining")
+                                end
                             end
                         end
-                    end
-                    {name}_protocol_fields({name}_protocol_fields_table, "{name}")
+                        "#,
+                    )?;
+                }
+                match framing {
+                    Framing::Single => {}
+                    Framing::Tcp => {
+                        PduLen::for_target(self)
+                            .expect("PDU length should be checked before generating the dissector")
+                            .write_pdu_len_fn(writer, name)?;
+                        write_tcp_dissector(writer, name, &dissect_pdu_fn, append_info)?;
+                    }
+                    Framing::Multiple => write_multiple_dissector(
+                        writer,
+                        name,
+                        &dissect_pdu_fn,
+                        &trailing_bytes,
+                        append_info,
+                    )?,
+                }
+                writeln!(
+                    writer,
+                    r#"{name}_protocol_fields({name}_protocol_fields_table, "{filter_prefix}")"#
+                )?;
+                if self.has_child_packets() {
+                    writedoc!(
+                        writer,
+                        r#"
+                        {name}_protocol_fields_table["{filter_prefix}._packet_chain_"] = AlignedProtoField:new({{
+                            name = "Packet chain",
+                            abbr = "{filter_prefix}._packet_chain_",
+                            ftype = ftypes.STRING,
+                            description = "The child packets that this packet is dissected as, outermost first",
+                        }})
+                        "#,
+                    )?;
+                }
+                writedoc!(
+                    writer,
+                    r#"
                     for name,field in pairs({name}_protocol_fields_table) do
                         {name}_protocol.fields[name] = field.field
                     end
+                    for name,expert in pairs({name}_protocol_experts) do
+                        {name}_protocol.experts[name] = expert
+                    end
                     "#,
                 )?;
             }
@@ -215,29 +415,51 @@ impl DeclDissectorInfo {
             DeclDissectorInfo::Sequence {
                 name,
                 fields,
-                children: _,
                 constraints,
+                ..
             } => {
                 writedoc!(
                     writer,
                     r#"
                     -- {comments}
-                    function {name}_dissect(buffer, pinfo, tree, fields, path)
+                    function {name}_dissect(buffer, pinfo, tree, fields, experts, path, field_values)
                         local i = 0
-                        local field_values = {{}}
+                        field_values = field_values or {{}}
+                        local current_field = nil
                     "#,
                     comments = self.to_comments(),
                 )?;
+                if self.has_child_packets() {
+                    // Names of the child packets that the body was dissected as, joined by ".".
+                    writeln!(writer.indent(), "local packet_chain = nil")?;
+                }
+                // Errors raised while dissecting the fields, e.g. when reading past the end of the
+                // buffer, are reported here so that the caller can continue with the next field.
+                writeln!(writer.indent(), "local ok, err = pcall(function()")?;
+                let mut body_writer = IndentWriter::new("        ", &mut *writer);
                 for field in fields {
-                    field.write_dissect_fn(&mut writer.indent())?;
+                    writeln!(
+                        body_writer,
+                        r#"current_field = "{}""#,
+                        field.common().display_name
+                    )?;
+                    field.write_dissect_fn(&mut body_writer)?;
                 }
                 writedoc!(
-                    writer,
+                    writer.indent(),
                     r#"
-                        return i
+                    end)
+                    if not ok then
+                        i = report_dissect_error(tree, experts, buffer, i, "{name}", current_field, err)
                     end
                     "#
                 )?;
+                if self.has_child_packets() {
+                    writeln!(writer.indent(), "return i, packet_chain")?;
+                } else {
+                    writeln!(writer.indent(), "return i")?;
+                }
+                writeln!(writer, "end")?;
                 let constraints_lua = if constraints.is_empty() {
                     String::from("true")
                 } else {
@@ -269,10 +491,30 @@ impl DeclDissectorInfo {
             DeclDissectorInfo::Checksum { name, .. } => name,
         }
     }
+
+    /// Whether this declaration has a payload or body that can be dissected as one of its
+    /// children.
+    fn has_child_packets(&self) -> bool {
+        match self {
+            DeclDissectorInfo::Sequence { fields, .. } => fields.iter().any(|field| {
+                matches!(field, FieldDissectorInfo::Payload { children, .. } if !children.is_empty())
+            }),
+            DeclDissectorInfo::Enum { .. } => false,
+            DeclDissectorInfo::Checksum { .. } => false,
+        }
+    }
+
+    fn abbr(&self) -> &str {
+        match self {
+            DeclDissectorInfo::Sequence { abbr, .. } => abbr,
+            DeclDissectorInfo::Enum { name, .. } => name,
+            DeclDissectorInfo::Checksum { name, .. } => name,
+        }
+    }
 }
 
 impl DeclExt for Decl<analyzer::ast::Annotation> {
-    fn to_dissector_info(&self, scope: &Scope) -> DeclDissectorInfo {
+    fn to_dissector_info(&self, scope: &Scope, options: &DissectorOptions) -> DeclDissectorInfo {
         match &self.desc {
             DeclDesc::Enum { id, tags, width } => DeclDissectorInfo::Enum {
                 name: id.clone(),
@@ -286,46 +528,153 @@ impl DeclExt for Decl<analyzer::ast::Annotation> {
             DeclDesc::CustomField { id, .. }
             | DeclDesc::Packet { id, .. }
             | DeclDesc::Struct { id, .. }
-            | DeclDesc::Group { id, .. } => {
-                let mut bit_offset = BitLen(0);
-                DeclDissectorInfo::Sequence {
-                    name: id.clone(),
-                    fields: {
-                        let mut field_dissector_infos = vec![];
-                        let mut ctx = FieldContext::new(scope);
-                        for field in self.fields() {
-                            if let Some(dissector_info) = field.to_dissector_info(
-                                &mut ctx,
-                                &bit_offset,
-                                self,
-                                field_dissector_infos.last_mut(),
-                            ) {
-                                bit_offset.0 =
-                                    (bit_offset.0 + dissector_info.len().bit_offset().0) % 8;
-                                field_dissector_infos.push(dissector_info);
-                            }
-                        }
-                        field_dissector_infos
-                    },
-                    children: scope
-                        .iter_children(self)
-                        .map(|child| child.to_dissector_info(scope))
-                        .collect::<Vec<_>>(),
-                    constraints: self
-                        .constraints()
-                        .map(|constraint| constraint.to_dissector_info(scope, self))
-                        .collect::<Vec<_>>(),
+            | DeclDesc::Group { id, .. } => DeclDissectorInfo::Sequence {
+                name: id.clone(),
+                abbr: options.abbr_style.apply(id),
+                fields: sequence_fields(self, scope, options).0,
+                children: scope
+                    .iter_children(self)
+                    .map(|child| child.to_dissector_info(scope, options))
+                    .collect::<Vec<_>>(),
+                constraints: self
+                    .constraints()
+                    .map(|constraint| constraint.to_dissector_info(scope, options, self))
+                    .collect::<Vec<_>>(),
+            },
+            DeclDesc::Test { .. } => unimplemented!(),
+        }
+    }
+}
+
+/// Converts the fields of a packet, struct or group. Also returns the problems found in the
+/// annotations of the fields, which are reported by [`validate_annotations`].
+fn sequence_fields(
+    decl: &Decl<analyzer::ast::Annotation>,
+    scope: &Scope,
+    options: &DissectorOptions,
+) -> (Vec<FieldDissectorInfo>, Vec<Diagnostic<FileId>>) {
+    let mut bit_offset = BitLen(0);
+    let mut field_dissector_infos = vec![];
+    let mut ctx = FieldContext::new(scope, options);
+    for field in decl.fields() {
+        if let Some(dissector_info) = field.to_dissector_info(
+            &mut ctx,
+            &bit_offset,
+            decl,
+            field_dissector_infos.last_mut(),
+        ) {
+            bit_offset.0 = (bit_offset.0 + dissector_info.len().bit_offset().0) % 8;
+            field_dissector_infos.push(dissector_info);
+        }
+    }
+    (field_dissector_infos, ctx.diagnostics)
+}
+
+/// Checks that the file doesn't use PDL constructs that cannot be dissected.
+fn check_unsupported(file: &analyzer::ast::File) -> Result<(), Diagnostics> {
+    let diagnostics = file
+        .declarations
+        .iter()
+        .flat_map(|decl| decl.fields())
+        .filter_map(|field| match &field.desc {
+            // This field is undocumented in
+            // https://github.com/google/pdl/blob/main/doc/reference.md and untested in the PDL repo.
+            FieldDesc::ElementSize { .. } => Some(
+                Diagnostic::error()
+                    .with_message("`_elementsize_` fields are not supported")
+                    .with_labels(vec![field.loc.primary()]),
+            ),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(Diagnostics::from(diagnostics))
+    }
+}
+
+/// Checks the annotations of the fields in all declarations of the file.
+fn validate_annotations(
+    file: &analyzer::ast::File,
+    scope: &Scope,
+    options: &DissectorOptions,
+) -> Result<(), Diagnostics> {
+    let mut diagnostics = vec![];
+    for decl in &file.declarations {
+        // Fields of groups are checked where the groups are inlined.
+        if let DeclDesc::CustomField { .. } | DeclDesc::Packet { .. } | DeclDesc::Struct { .. } =
+            decl.desc
+        {
+            for diagnostic in sequence_fields(decl, scope, options).1 {
+                if !diagnostics.contains(&diagnostic) {
+                    diagnostics.push(diagnostic);
                 }
             }
-            DeclDesc::Test { .. } => unimplemented!(),
         }
     }
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(Diagnostics::from(diagnostics))
+    }
+}
+
+/// Warns about children of packets with a body that can never be dissected, because a child that
+/// is checked before them matches whenever they do.
+fn check_child_constraints(
+    file: &analyzer::ast::File,
+    scope: &Scope,
+    options: &DissectorOptions,
+) -> Vec<Diagnostic<FileId>> {
+    let mut diagnostics = vec![];
+    for decl in &file.declarations {
+        if !decl
+            .fields()
+            .any(|field| matches!(field.desc, FieldDesc::Body))
+        {
+            continue;
+        }
+        let children = scope
+            .iter_children(decl)
+            .map(|child| {
+                let constraints = child
+                    .constraints()
+                    .map(|constraint| constraint.to_dissector_info(scope, options, child))
+                    .collect::<Vec<_>>();
+                (child, constraints)
+            })
+            .collect::<Vec<_>>();
+        for (j, (child, constraints)) in children.iter().enumerate() {
+            let shadowing_child = children[..j].iter().find(|(_, earlier_constraints)| {
+                earlier_constraints.iter().all(|c| constraints.contains(c))
+            });
+            if let Some((earlier_child, _)) = shadowing_child {
+                let name = child.id().unwrap_or_default();
+                let earlier_name = earlier_child.id().unwrap_or_default();
+                diagnostics.push(
+                    Diagnostic::warning()
+                        .with_message(format!(
+                            "`{name}` cannot be told apart from `{earlier_name}` by its constraints",
+                        ))
+                        .with_labels(vec![
+                            child.loc.primary().with_message(format!(
+                                "never dissected, because `{earlier_name}` matches first"
+                            )),
+                            earlier_child.loc.secondary(),
+                        ]),
+                );
+            }
+        }
+    }
+    diagnostics
 }
 
 trait ConstraintExt {
     fn to_dissector_info(
         &self,
         scope: &Scope,
+        options: &DissectorOptions,
         decl: &Decl<analyzer::ast::Annotation>,
     ) -> ConstraintDissectorInfo;
 }
@@ -334,6 +683,7 @@ impl ConstraintExt for Constraint {
     fn to_dissector_info(
         &self,
         scope: &Scope,
+        options: &DissectorOptions,
         decl: &Decl<analyzer::ast::Annotation>,
     ) -> ConstraintDissectorInfo {
         match self {
@@ -343,7 +693,7 @@ impl ConstraintExt for Constraint {
                 value: Some(v),
                 tag_id: None,
             } => ConstraintDissectorInfo::ValueMatch {
-                field: id.clone(),
+                field: options.abbr_style.apply(id),
                 value: *v,
             },
             Constraint {
@@ -366,7 +716,7 @@ impl ConstraintExt for Constraint {
                 }
                 let parent_decl = scope.get_parent(decl).unwrap();
                 ConstraintDissectorInfo::EnumMatch {
-                    field: id.clone(),
+                    field: options.abbr_style.apply(id),
                     enum_type: find_ancestor_field(scope, parent_decl, |f| f.id() == Some(id))
                         .map(|f| match &f.desc {
                             FieldDesc::Typedef { id: _, type_id } => type_id.clone(),
@@ -383,7 +733,7 @@ impl ConstraintExt for Constraint {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ConstraintDissectorInfo {
     EnumMatch {
         field: String,
@@ -413,6 +763,36 @@ impl ConstraintDissectorInfo {
             }
         }
     }
+
+    /// The field that this constraint checks.
+    fn field(&self) -> &str {
+        match self {
+            ConstraintDissectorInfo::EnumMatch { field, .. }
+            | ConstraintDissectorInfo::ValueMatch { field, .. } => field,
+        }
+    }
+
+    /// Describes the expected value, e.g. `type=Simple`.
+    fn description(&self) -> String {
+        match self {
+            ConstraintDissectorInfo::EnumMatch {
+                field, enum_value, ..
+            } => format!("{field}={enum_value}"),
+            ConstraintDissectorInfo::ValueMatch { field, value } => format!("{field}={value}"),
+        }
+    }
+}
+
+impl Validation {
+    /// A Lua expression that is true if the variable `value` passes this check.
+    fn to_lua_expr(&self) -> String {
+        match self {
+            Validation::Equals(value) => format!("value == {value}"),
+            Validation::EnumTag { enum_id, tag_id } => {
+                format!(r#"{enum_id}_enum:match("{tag_id}", value)"#)
+            }
+        }
+    }
 }
 
 trait FieldExt {
@@ -440,12 +820,50 @@ pub struct CommonFieldDissectorInfo {
 pub struct ArrayFieldDissectorInfo {
     /// Number of items in the array, or `None` if the array is unbounded
     count: Option<usize>,
-    size_modifier: Option<String>,
+    /// Number of bytes added to the value of the size field, written as `[+N]` in PDL.
+    size_modifier: Option<usize>,
     pad_to_size: Option<usize>,
     has_size_field: bool,
     has_count_field: bool,
 }
 
+impl ArrayFieldDissectorInfo {
+    /// Returns a Lua expression for the length in bytes of all items of the array `abbr`, or
+    /// `nil` if the array takes the rest of the buffer.
+    fn total_len_expr(&self, abbr: &str, item_len: BitLen) -> String {
+        let item_bytes = item_len.0 / 8;
+        if let Some(count) = self.count {
+            (count * item_bytes).to_string()
+        } else if self.has_count_field {
+            format!(r#"field_values[path .. ".{abbr}_count"] * {item_bytes}"#)
+        } else if self.has_size_field {
+            format!(
+                r#"field_values[path .. ".{abbr}_size"]{size_modifier}"#,
+                size_modifier = self.size_modifier_expr(),
+            )
+        } else {
+            "nil".into()
+        }
+    }
+
+    /// Returns the Lua code to append to the value of the size field, e.g. `+2`.
+    fn size_modifier_expr(&self) -> String {
+        self.size_modifier
+            .map(|size_modifier| format!("+{size_modifier}"))
+            .unwrap_or_default()
+    }
+}
+
+/// A child declaration that a payload or body field may be dissected as.
+#[derive(Debug, Clone)]
+pub struct ChildDissectorInfo {
+    name: String,
+    /// Filter name segment of the child declaration.
+    abbr: String,
+    /// The constraints on the fields of the parent that select this child.
+    constraints: Vec<ConstraintDissectorInfo>,
+}
+
 #[derive(Debug, Clone)]
 pub enum FieldDissectorInfo {
     Scalar {
@@ -453,10 +871,10 @@ pub enum FieldDissectorInfo {
         ftype: FType,
         /// The length this field takes before repetition.
         len: RuntimeLenInfo,
-        /// A lua-expression that yields a boolean value. If the boolean result
-        /// is false, a warning will be shown in the dissected info. `value` is
-        /// a variable that can be used to get the value of this field.
-        validate_expr: Option<String>,
+        display: FieldDisplay,
+        /// A check on the value of this field. If it fails, a warning will be shown in the
+        /// dissected info.
+        validation: Option<Validation>,
         /// (optional field name, match value)
         optional_field: Option<(String, usize)>,
     },
@@ -465,7 +883,11 @@ pub enum FieldDissectorInfo {
         ftype: FType,
         /// The length this field takes before repetition.
         len: RuntimeLenInfo,
-        children: Vec<String>,
+        children: Vec<ChildDissectorInfo>,
+        /// Name of the declaration that the payload belongs to.
+        parent: String,
+        /// The dissector that the bytes of the payload are handed to.
+        handoff: Option<Handoff>,
     },
     Typedef {
         common: CommonFieldDissectorInfo,
@@ -483,10 +905,23 @@ pub enum FieldDissectorInfo {
         ftype: FType,
         item_len: BitLen,
         array_info: ArrayFieldDissectorInfo,
+        display: FieldDisplay,
+        /// The dissector that the bytes of the array are handed to.
+        handoff: Option<Handoff>,
     },
 }
 
 impl FieldDissectorInfo {
+    fn common(&self) -> &CommonFieldDissectorInfo {
+        match self {
+            FieldDissectorInfo::Scalar { common, .. }
+            | FieldDissectorInfo::Payload { common, .. }
+            | FieldDissectorInfo::Typedef { common, .. }
+            | FieldDissectorInfo::TypedefArray { common, .. }
+            | FieldDissectorInfo::ScalarArray { common, .. } => common,
+        }
+    }
+
     pub fn to_comments(&self) -> String {
         if log::log_enabled!(log::Level::Debug) {
             format!("{self:?}")
@@ -544,45 +979,84 @@ impl FieldDissectorInfo {
                     endian,
                     comments,
                 } = common;
-                let bitlen = ftype
-                    .0
-                    .map(|v| v.to_string())
-                    .unwrap_or_else(|| String::from("nil"));
-                if self.is_unaligned() {
-                    writedoc!(
-                        writer,
-                        r#"
-                        fields[path .. ".{abbr}"] = UnalignedProtoField:new({{
-                            name = "{display_name}",
-                            abbr = path .. ".{abbr}",
-                            ftype = {ftype},
-                            bitoffset = {bit_offset},
-                            bitlen = {bitlen},
-                            is_little_endian = {is_le},
-                            description = {description},
-                        }})
-                        "#,
-                        ftype = ftype.to_lua_expr(),
-                        is_le = *endian == EndiannessValue::LittleEndian,
-                        description = comments.as_deref().to_lua_expr(),
-                    )?;
+                let display = match self {
+                    FieldDissectorInfo::Scalar { display, .. }
+                    | FieldDissectorInfo::ScalarArray { display, .. } => Some(display),
+                    FieldDissectorInfo::Payload { .. } => None,
+                    _ => unreachable!(),
+                };
+                let mut properties = vec![
+                    ("name", format!(r#""{display_name}""#)),
+                    ("abbr", format!(r#"path .. ".{abbr}""#)),
+                ];
+                match display {
+                    Some(FieldDisplay::Boolean {
+                        true_string,
+                        false_string,
+                    }) => {
+                        properties.push(("ftype", "ftypes.BOOLEAN".into()));
+                        properties.push((
+                            "valuestring",
+                            format!(
+                                "{{{}, {}}}",
+                                lua_string(true_string),
+                                lua_string(false_string)
+                            ),
+                        ));
+                    }
+                    Some(FieldDisplay::Typed(display_type)) => {
+                        properties.push(("ftype", display_type.to_lua_expr().into()));
+                    }
+                    Some(FieldDisplay::Integer { base, unit }) => {
+                        properties.push(("ftype", ftype.to_lua_expr().into()));
+                        if let Some(base) = base {
+                            properties.push(("base", base.to_lua_expr().into()));
+                        }
+                        if let Some(unit) = unit {
+                            properties.push(("unit", lua_string(&format!(" {unit}"))));
+                        }
+                    }
+                    None => properties.push(("ftype", ftype.to_lua_expr().into())),
+                }
+                let proto_field = if self.is_unaligned() {
+                    properties.push(("bitoffset", bit_offset.to_string()));
+                    "UnalignedProtoField"
                 } else {
-                    writedoc!(
-                        writer,
-                        r#"
-                        fields[path .. ".{abbr}"] = AlignedProtoField:new({{
-                            name = "{display_name}",
-                            abbr = path .. ".{abbr}",
-                            ftype = {ftype},
-                            bitlen = {bitlen},
-                            is_little_endian = {is_le},
-                            description = {description},
-                        }})
-                        "#,
-                        ftype = ftype.to_lua_expr(),
-                        is_le = *endian == EndiannessValue::LittleEndian,
-                        description = comments.as_deref().to_lua_expr(),
-                    )?;
+                    "AlignedProtoField"
+                };
+                // Typed arrays are dissected as a single item of variable length.
+                let bitlen = match (display, ftype.0) {
+                    (Some(FieldDisplay::Typed(_)), _) | (_, None) => String::from("nil"),
+                    (_, Some(bitlen)) => bitlen.to_string(),
+                };
+                properties.push(("bitlen", bitlen));
+                // Addresses are in network byte order even in little endian files.
+                let is_little_endian = *endian == EndiannessValue::LittleEndian
+                    && !matches!(display, Some(FieldDisplay::Typed(display_type)) if display_type.is_network_order());
+                properties.push(("is_little_endian", is_little_endian.to_string()));
+                properties.push(("description", comments.as_deref().to_lua_expr()));
+                writeln!(
+                    writer,
+                    r#"fields[path .. ".{abbr}"] = {proto_field}:new({{"#
+                )?;
+                for (key, value) in properties {
+                    writeln!(writer.indent(), "{key} = {value},")?;
+                }
+                writeln!(writer, "}})")?;
+                if let FieldDissectorInfo::Payload { children, .. } = self {
+                    if !children.is_empty() {
+                        writedoc!(
+                            writer,
+                            r#"
+                            fields[path .. "._packet_"] = AlignedProtoField:new({{
+                                name = "Packet",
+                                abbr = path .. "._packet_",
+                                ftype = ftypes.STRING,
+                                description = "The child packet that `{display_name}` is dissected as",
+                            }})
+                            "#,
+                        )?;
+                    }
                 }
             }
             FieldDissectorInfo::Typedef { common, decl, .. }
@@ -595,10 +1069,13 @@ impl FieldDissectorInfo {
                     comments,
                 } = common;
                 match decl.as_ref() {
-                    DeclDissectorInfo::Sequence { fields, .. } => {
-                        for field in fields {
-                            field.field_declaration(writer)?;
-                        }
+                    DeclDissectorInfo::Sequence {
+                        name: type_name, ..
+                    } => {
+                        writeln!(
+                            writer,
+                            r#"{type_name}_protocol_fields(fields, path .. ".{abbr}")"#
+                        )?;
                     }
                     DeclDissectorInfo::Enum {
                         name: type_name,
@@ -664,6 +1141,19 @@ impl FieldDissectorInfo {
                         )?;
                     }
                 }
+                if let FieldDissectorInfo::TypedefArray { .. } = self {
+                    writedoc!(
+                        writer,
+                        r#"
+                        fields[path .. ".{abbr}_item_count"] = AlignedProtoField:new({{
+                            name = "Item count",
+                            abbr = path .. ".{abbr}_item_count",
+                            ftype = ftypes.UINT32,
+                            description = "The number of items in `{display_name}`",
+                        }})
+                        "#,
+                    )?;
+                }
             }
         }
         Ok(())
@@ -686,7 +1176,7 @@ impl FieldDissectorInfo {
         match self {
             FieldDissectorInfo::Scalar {
                 common,
-                validate_expr,
+                validation,
                 optional_field,
                 ..
             } => match optional_field {
@@ -699,20 +1189,18 @@ impl FieldDissectorInfo {
                     )?;
                     self.write_scalar_dissect(
                         &mut writer.indent(),
-                        &common.abbr,
+                        common,
                         &[],
-                        validate_expr.clone(),
+                        validation.as_ref(),
                     )?;
                     writeln!(writer, "end")?;
                 }
-                None => {
-                    self.write_scalar_dissect(writer, &common.abbr, &[], validate_expr.clone())?
-                }
+                None => self.write_scalar_dissect(writer, common, &[], validation.as_ref())?,
             },
             FieldDissectorInfo::Payload {
                 common, children, ..
             } => {
-                self.write_scalar_dissect(writer, &common.abbr, children, None)?;
+                self.write_scalar_dissect(writer, common, children, None)?;
             }
             FieldDissectorInfo::Typedef {
                 common,
@@ -729,7 +1217,8 @@ impl FieldDissectorInfo {
                     self.write_typedef_dissect(
                         &mut writer.indent(),
                         decl,
-                        &common.display_name,
+                        "tree",
+                        &format!(r#""{}""#, common.display_name),
                         &common.abbr,
                         common.endian,
                     )?;
@@ -738,7 +1227,8 @@ impl FieldDissectorInfo {
                 None => self.write_typedef_dissect(
                     writer,
                     decl,
-                    &common.display_name,
+                    "tree",
+                    &format!(r#""{}""#, common.display_name),
                     &common.abbr,
                     common.endian,
                 )?,
@@ -751,25 +1241,79 @@ impl FieldDissectorInfo {
                 let CommonFieldDissectorInfo {
                     display_name, abbr, ..
                 } = common;
+                // The items are added into a subtree for the whole array, which is labeled with the
+                // number of items and bytes once they are dissected.
+                writeln!(
+                    writer,
+                    r#"local array_tree = tree:add(buffer(i, 0), "{display_name}")"#
+                )?;
                 self.write_array_dissect(writer, common, array_info, |w| {
-                    self.write_typedef_dissect(w, decl, display_name, abbr, common.endian)
+                    self.write_typedef_dissect(
+                        w,
+                        decl,
+                        "array_tree",
+                        &format!(r#""{display_name}[" .. index .. "]""#),
+                        abbr,
+                        common.endian,
+                    )?;
+                    if let DeclDissectorInfo::Enum { .. } = decl.as_ref() {
+                        writeln!(w, r#"subtree:prepend_text("[" .. index .. "] ")"#)?;
+                    }
+                    Ok(())
                 })?;
+                writedoc!(
+                    writer,
+                    r#"
+                    array_tree:set_len(array_len)
+                    array_tree:append_text(" (" .. item_count .. (item_count == 1 and " item, " or " items, ") .. string.format("%g", array_len) .. " bytes)")
+                    array_tree:add(fields[path .. ".{abbr}_item_count"].field, item_count):set_generated()
+                    "#
+                )?;
                 if let Some(octet_size) = array_info.pad_to_size {
                     writedoc!(
                         writer,
                         r#"
                         if i - initial_i < {octet_size} then
-                            tree:add_expert_info(PI_MALFORMED, PI_WARN, "Error: Expected a minimum of {octet_size} octets in field `{display_name}`")
+                            if i >= buffer:len() then
+                                check_truncated(buffer, initial_i, {octet_size})
+                            end
+                            tree:add_proto_expert_info({padding_short}, "Error: Expected a minimum of {octet_size} octets in field `{display_name}`")
                         end
-                        "#
+                        "#,
+                        padding_short = Expert::PaddingShort.to_lua_expr(),
                     )?;
                 }
             }
+            FieldDissectorInfo::ScalarArray {
+                common,
+                array_info,
+                item_len,
+                display: FieldDisplay::Typed(_),
+                handoff,
+                ..
+            } => {
+                let len_expr = array_info.total_len_expr(&common.abbr, *item_len);
+                writedoc!(
+                    writer,
+                    r#"
+                    -- {comments}
+                    local field_len = enforce_len_limit({len_expr}, buffer(i), tree, experts)
+                    subtree, field_values[path .. ".{abbr}"], bitlen = fields[path .. ".{abbr}"]:dissect(tree, buffer(i), field_len)
+                    "#,
+                    comments = self.to_comments(),
+                    abbr = common.abbr,
+                )?;
+                if let Some(handoff) = handoff {
+                    handoff.write_call(writer)?;
+                }
+                writeln!(writer, "i = i + bitlen / 8")?;
+            }
             FieldDissectorInfo::ScalarArray {
                 common, array_info, ..
             } => {
                 self.write_array_dissect(writer, common, array_info, |w| {
-                    self.write_scalar_dissect(w, &common.abbr, &[], None)
+                    self.write_scalar_dissect(w, common, &[], None)?;
+                    writeln!(w, r#"subtree:prepend_text("[" .. index .. "] ")"#)
                 })?;
             }
         }
@@ -779,64 +1323,82 @@ impl FieldDissectorInfo {
     pub fn write_scalar_dissect(
         &self,
         writer: &mut impl std::io::Write,
-        abbr: &str,
-        children: &[String],
-        validate_expr: Option<String>,
+        common: &CommonFieldDissectorInfo,
+        children: &[ChildDissectorInfo],
+        validation: Option<&Validation>,
     ) -> std::io::Result<()> {
+        let CommonFieldDissectorInfo {
+            display_name, abbr, ..
+        } = common;
         let len_expr = self.len().to_lua_expr();
         writedoc!(
             writer,
             r#"
             -- {comments}
-            local field_len = enforce_len_limit({len_expr}, buffer(i):len(), tree)
+            local field_len = enforce_len_limit({len_expr}, buffer(i), tree, experts)
             "#,
             comments = self.to_comments()
         )?;
         lua_if_then_else(
             &mut *writer,
-            children.iter().map(|child_name| {
+            children.iter().map(|ChildDissectorInfo { name: child_name, abbr: child_abbr, .. }| {
+                let len_expr = &len_expr;
                 (
                     format!("{child_name}_match_constraints(field_values, path)"),
                     move |w: &mut dyn std::io::Write| writedoc!(
                         w,
                         r#"
-                        local subtree = tree:add("{child_name}")
-                        local dissected_len = {child_name}_dissect(buffer(i, field_len), pinfo, subtree, fields, path .. ".{child_name}")
-                        i = i + dissected_len
+                        local subtree = tree:add(fields[path .. "._packet_"].field, buffer(i, field_len), "{child_name}")
+                        local dissected_len, child_packet_chain = {child_name}_dissect(field_buffer(buffer, i, field_len, {len_expr}), pinfo, subtree, fields, experts, path .. ".{child_abbr}")
+                        packet_chain = "{child_name}" .. (child_packet_chain and ("." .. child_packet_chain) or "")
+                        subtree:set_len(dissected_len)
+                        i = check_consumed_len(tree, experts, buffer, "Size({display_name})", {len_expr}, i, i + dissected_len)
                         "#,
                     )
                 )
             }),
             Some(|w: &mut dyn std::io::Write| {
-                writedoc!(
+                writeln!(
                     w,
-                    r#"
-                    subtree, field_values[path .. ".{abbr}"], bitlen = fields[path .. ".{abbr}"]:dissect(tree, buffer(i), field_len)
-                    i = i + bitlen / 8
-                    "#,
+                    r#"subtree, field_values[path .. ".{abbr}"], bitlen = fields[path .. ".{abbr}"]:dissect(tree, buffer(i), field_len)"#,
                 )?;
-                if let Some(validate) = validate_expr.as_ref() {
+                if let FieldDissectorInfo::Payload {
+                    handoff: Some(handoff),
+                    ..
+                } = self
+                {
+                    handoff.write_call(w)?;
+                }
+                writeln!(w, "i = i + bitlen / 8")?;
+                if let Some(validate) = validation.map(Validation::to_lua_expr) {
                     writedoc!(
                         w,
                         r#"
                         local value = field_values[path .. ".{abbr}"]
                         if not ({validate}) then
-                            subtree:add_expert_info(PI_MALFORMED, PI_WARN, "Error: Expected `{validate_escaped}` where value=" .. tostring(value))
+                            subtree:add_proto_expert_info({fixed_mismatch}, "Error: Expected `{validate_escaped}` where value=" .. tostring(value))
                         end
                         "#,
+                        fixed_mismatch = Expert::FixedMismatch.to_lua_expr(),
                         validate_escaped = validate.replace('\\', "\\\\").replace('"', "\\\"")
                     )?;
                 }
+                if let FieldDissectorInfo::Payload { parent, .. } = self {
+                    write_no_child_matched_note(w, parent, children)?;
+                }
                 Ok(())
             }))?;
         Ok(())
     }
 
+    /// Writes the dissection of a typedef field into `tree`. `label` is the Lua expression of the
+    /// label of the subtree of a struct.
     pub fn write_typedef_dissect(
         &self,
         writer: &mut impl std::io::Write,
         decl: &DeclDissectorInfo,
-        name: &str,
+        tree: &str,
+        label: &str,
         abbr: &str,
         endian: EndiannessValue,
     ) -> std::io::Result<()> {
@@ -849,11 +1411,11 @@ impl FieldDissectorInfo {
                     writer,
                     r#"
                     -- {comments}
-                    local field_len = enforce_len_limit({len_expr}, buffer(i):len(), tree)
-                    local subtree = tree:add(buffer(i, field_len), "{name}")
-                    local dissected_len = {type_name}_dissect(buffer(i, field_len), pinfo, subtree, fields, path)
+                    local field_len = enforce_len_limit({len_expr}, buffer(i), tree, experts)
+                    local subtree = {tree}:add(buffer(i, field_len), {label})
+                    local dissected_len = {type_name}_dissect(field_buffer(buffer, i, field_len, {len_expr}), pinfo, subtree, fields, experts, path .. ".{abbr}")
                     subtree:set_len(dissected_len)
-                    i = i + dissected_len
+                    i = check_consumed_len({tree}, experts, buffer, "Size(" .. {label} .. ")", {len_expr}, i, i + dissected_len)
                     "#,
                     comments = self.to_comments(),
                 )?;
@@ -866,13 +1428,14 @@ impl FieldDissectorInfo {
                     writer,
                     r#"
                     -- {comments}
-                    local field_len = enforce_len_limit(math.ceil({len_expr}), buffer(i):len(), tree)
-                    subtree, field_values[path .. ".{name}"], bitlen = fields[path .. ".{abbr}"]:dissect(tree, buffer(i), field_len)
-                    if {type_name}_enum.by_value[field_values[path .. ".{name}"]] == nil then
-                        tree:add_expert_info(PI_MALFORMED, PI_WARN, "Unknown enum value: " .. field_values[path .. ".{name}"])
+                    local field_len = enforce_len_limit(math.ceil({len_expr}), buffer(i), tree, experts)
+                    subtree, field_values[path .. ".{abbr}"], bitlen = fields[path .. ".{abbr}"]:dissect({tree}, buffer(i), field_len)
+                    if {type_name}_enum.by_value[field_values[path .. ".{abbr}"]] == nil then
+                        {tree}:add_proto_expert_info({unknown_enum}, "Unknown enum value: " .. field_values[path .. ".{abbr}"])
                     end
                     i = i + bitlen / 8
                     "#,
+                    unknown_enum = Expert::UnknownEnum.to_lua_expr(),
                     comments = self.to_comments(),
                 )?;
             }
@@ -891,8 +1454,8 @@ impl FieldDissectorInfo {
                     writer,
                     r#"
                     -- {comments}
-                    local field_len = enforce_len_limit({len_expr}, buffer(i):len(), tree)
-                    field_values[path .. ".{name}"] = buffer(i, field_len):{buffer_value_function}
+                    local field_len = enforce_len_limit({len_expr}, buffer(i), tree, experts)
+                    field_values[path .. ".{abbr}"] = buffer(i, field_len):{buffer_value_function}
                     if field_len ~= 0 then
                         tree:{add_fn}(fields[path .. ".{abbr}"].field, buffer(i, field_len))
                         i = i + field_len
@@ -943,6 +1506,7 @@ impl FieldDissectorInfo {
             r#"
             -- {comments}
             local initial_i = i
+            local item_count = 0
             "#,
             comments = self.to_comments(),
         )?;
@@ -953,10 +1517,12 @@ impl FieldDissectorInfo {
                 for j=1,field_values[path .. ".{abbr}_count"] do
                     -- Warn if there isn't enough elements to fit the expected count
                     if i >= buffer:len() and j <= {count} then
-                        tree:add_expert_info(PI_MALFORMED, PI_WARN, "Error: Expected " .. {count} .. " `{display_name}` items but only found " .. (j - 1))
+                        check_truncated(buffer, i, nil)
+                        tree:add_proto_expert_info({array_count_short}, "Error: Expected " .. {count} .. " `{display_name}` items but only found " .. (j - 1))
                         break
                     end
                 "#,
+                array_count_short = Expert::ArrayCountShort.to_lua_expr(),
                 count = format!(r#"field_values[path .. ".{abbr}_count"]"#),
             )?;
         } else if let Some(count) = count {
@@ -966,10 +1532,12 @@ impl FieldDissectorInfo {
                 for j=1,{count} do
                     -- Warn if there isn't enough elements to fit the expected count
                     if i >= buffer:len() and j <= {count} then
-                        tree:add_expert_info(PI_MALFORMED, PI_WARN, "Error: Expected {count} `{display_name}` items but only found " .. (j - 1))
+                        check_truncated(buffer, i, nil)
+                        tree:add_proto_expert_info({array_count_short}, "Error: Expected {count} `{display_name}` items but only found " .. (j - 1))
                         break
                     end
-                "#
+                "#,
+                array_count_short = Expert::ArrayCountShort.to_lua_expr(),
             )?;
         } else if array_info.has_size_field {
             // Check that the array doesn't exceed the size() field
@@ -977,21 +1545,110 @@ impl FieldDissectorInfo {
                 writer,
                 r#"
                 if initial_i + field_values[path .. ".{abbr}_size"]{size_modifier} > buffer:len() then
-                    tree:add_expert_info(PI_MALFORMED, PI_WARN, "Error: Size({display_name}) is greater than the number of remaining bytes")
+                    check_truncated(buffer, initial_i, field_values[path .. ".{abbr}_size"]{size_modifier})
+                    tree:add_proto_expert_info({length_overrun}, "Error: Size({display_name}) is greater than the number of remaining bytes")
                 end
                 while i < buffer:len() and i - initial_i < field_values[path .. ".{abbr}_size"]{size_modifier} do
                 "#,
-                size_modifier = size_modifier.as_deref().unwrap_or_default(),
+                length_overrun = Expert::LengthOverrun.to_lua_expr(),
+                size_modifier = array_info.size_modifier_expr(),
             )?;
         } else {
-            writedoc!(writer, r#"while i < buffer:len() do"#)?;
+            writeln!(writer, "while i < buffer:len() do")?;
+        }
+        // Stop at `ARRAY_MAX_COUNT` items, so that malformed captures can't make the loop run
+        // for too long.
+        writedoc!(
+            writer.indent(),
+            r#"
+            if item_count >= ARRAY_MAX_COUNT then
+                tree:add_proto_expert_info({array_stopped}, "Error: Stopped dissecting `{display_name}` after " .. ARRAY_MAX_COUNT .. " items")
+                break
+            end
+            "#,
+            array_stopped = Expert::ArrayStopped.to_lua_expr(),
+        )?;
+        // `index` is the index of the item being dissected, which `write_item_dissect` can use to
+        // label it.
+        writeln!(writer.indent(), "local index = item_count")?;
+        // Loops that run until the end of the array would never end on an empty item.
+        let check_progress = !array_info.has_count_field && count.is_none();
+        if check_progress {
+            writeln!(writer.indent(), "local item_start = i")?;
         }
         write_item_dissect(&mut writer.indent())?;
-        writeln!(writer, "end")?;
-        Ok(())
-    }
-}
-
+        writeln!(writer.indent(), "item_count = item_count + 1")?;
+        if check_progress {
+            writedoc!(
+                writer.indent(),
+                r#"
+                if i == item_start then
+                    tree:add_proto_expert_info({array_stopped}, "Error: Stopped dissecting `{display_name}` at an item of length 0")
+                    break
+                end
+                "#,
+                array_stopped = Expert::ArrayStopped.to_lua_expr(),
+            )?;
+        }
+        writeln!(writer, "end")?;
+        if array_info.has_size_field {
+            writedoc!(
+                writer,
+                r#"
+                local array_len = i - initial_i
+                i = check_consumed_len(tree, experts, buffer, "Size({display_name})", field_values[path .. ".{abbr}_size"]{size_modifier}, initial_i, i)
+                "#,
+                size_modifier = array_info.size_modifier_expr(),
+            )?;
+        } else {
+            writeln!(writer, "local array_len = i - initial_i")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes an expert note saying which values of the fields in the constraints of `children` made
+/// none of them match, and which values each of them expects.
+fn write_no_child_matched_note(
+    writer: &mut dyn std::io::Write,
+    parent: &str,
+    children: &[ChildDissectorInfo],
+) -> std::io::Result<()> {
+    let mut fields = vec![];
+    for constraint in children.iter().flat_map(|child| &child.constraints) {
+        if !fields.contains(&constraint.field()) {
+            fields.push(constraint.field());
+        }
+    }
+    if fields.is_empty() {
+        return Ok(());
+    }
+    let values = fields
+        .iter()
+        .map(|field| format!(r#""{field}=" .. tostring(field_values[path .. ".{field}"])"#))
+        .collect::<Vec<_>>()
+        .join(r#" .. ", " .. "#);
+    let expected = children
+        .iter()
+        .map(|child| {
+            let constraints = child
+                .constraints
+                .iter()
+                .map(|c| c.description())
+                .collect::<Vec<_>>();
+            format!("{}: {}", child.name, constraints.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+    writedoc!(
+        writer,
+        r#"
+        subtree:add_proto_expert_info({no_child_matched}, {values} .. " matched no child of {parent} (expected {expected})")
+        "#,
+        no_child_matched = Expert::NoChildMatched.to_lua_expr(),
+    )
+}
+
 impl FieldExt for Field<analyzer::ast::Annotation> {
     fn to_dissector_info(
         &self,
@@ -1004,6 +1661,8 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
             "Write field: {:?}\nannot={:?}\ndecl={:?}",
             self, self.annot, decl
         );
+        let display = ctx.display(self, decl, *bit_offset);
+        let handoff = ctx.handoff(self, decl);
         match &self.desc {
             FieldDesc::Checksum { field_id: _ } => {
                 // This is the `_checksum_start_` field.
@@ -1039,15 +1698,15 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
                                 _ => field_id,
                             }
                         ),
-                        abbr: format!("{field_id}_size"),
+                        abbr: format!("{}_size", ctx.abbr(field_id)),
                         bit_offset: *bit_offset,
                         endian: ctx.scope.file.endianness.value,
-                        comments: find_comments_on_same_line(ctx.scope.file, &self.loc)
-                            .map(|comment| unwrap_comment(&comment.text).to_string()),
+                        comments: field_description(ctx.scope.file, &self.loc),
                     },
                     ftype,
                     len: RuntimeLenInfo::fixed(BitLen(*width)),
-                    validate_expr: None,
+                    display: FieldDisplay::default(),
+                    validation: None,
                     optional_field: None,
                 })
             }
@@ -1062,15 +1721,15 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
                                 _ => field_id,
                             }
                         ),
-                        abbr: format!("{field_id}_count"),
+                        abbr: format!("{}_count", ctx.abbr(field_id)),
                         bit_offset: *bit_offset,
                         endian: ctx.scope.file.endianness.value,
-                        comments: find_comments_on_same_line(ctx.scope.file, &self.loc)
-                            .map(|comment| unwrap_comment(&comment.text).to_string()),
+                        comments: field_description(ctx.scope.file, &self.loc),
                     },
                     ftype,
-                    len: RuntimeLenInfo::fixed(BitLen(width * 8)),
-                    validate_expr: None,
+                    len: RuntimeLenInfo::fixed(BitLen(*width)),
+                    display: FieldDisplay::default(),
+                    validation: None,
                     optional_field: None,
                 })
             }
@@ -1084,7 +1743,18 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
                 let children = ctx
                     .scope
                     .iter_children(decl)
-                    .filter_map(|child_decl| child_decl.id().map(|c| c.to_string()))
+                    .filter_map(|child_decl| {
+                        child_decl.id().map(|c| ChildDissectorInfo {
+                            name: c.to_string(),
+                            abbr: ctx.abbr(c),
+                            constraints: child_decl
+                                .constraints()
+                                .map(|constraint| {
+                                    constraint.to_dissector_info(ctx.scope, ctx.options, child_decl)
+                                })
+                                .collect(),
+                        })
+                    })
                     .collect::<Vec<_>>();
                 let ftype = FType::from(self.annot.size);
                 let mut field_len = RuntimeLenInfo::empty();
@@ -1095,21 +1765,21 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
                         abbr: "_body_".into(),
                         bit_offset: *bit_offset,
                         endian: ctx.scope.file.endianness.value,
-                        comments: find_comments_on_same_line(ctx.scope.file, &self.loc)
-                            .map(|comment| unwrap_comment(&comment.text).to_string()),
+                        comments: field_description(ctx.scope.file, &self.loc),
                     },
                     ftype,
                     len: field_len,
                     children,
+                    parent: decl.id().unwrap_or_default().to_string(),
+                    handoff,
                 })
             }
             FieldDesc::Payload { size_modifier } => {
                 let mut field_len = RuntimeLenInfo::empty();
                 field_len.add_len_field(
                     "_payload__size".into(),
-                    size_modifier
-                        .as_ref()
-                        .map(|s| BitLen(s.parse::<usize>().unwrap() * 8))
+                    parse_size_modifier(size_modifier)
+                        .map(|size_modifier| BitLen(size_modifier * 8))
                         .unwrap_or_default(),
                 );
                 Some(FieldDissectorInfo::Payload {
@@ -1118,12 +1788,13 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
                         abbr: "_payload_".into(),
                         bit_offset: *bit_offset,
                         endian: ctx.scope.file.endianness.value,
-                        comments: find_comments_on_same_line(ctx.scope.file, &self.loc)
-                            .map(|comment| unwrap_comment(&comment.text).to_string()),
+                        comments: field_description(ctx.scope.file, &self.loc),
                     },
                     ftype: FType::from(self.annot.size),
                     len: field_len,
                     children: vec![],
+                    parent: decl.id().unwrap_or_default().to_string(),
+                    handoff,
                 })
             }
             FieldDesc::FixedScalar { width, value } => {
@@ -1134,18 +1805,19 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
                         abbr: format!("_fixed_{}", ctx.num_fixed - 1),
                         bit_offset: *bit_offset,
                         endian: ctx.scope.file.endianness.value,
-                        comments: find_comments_on_same_line(ctx.scope.file, &self.loc)
-                            .map(|comment| unwrap_comment(&comment.text).to_string()),
+                        comments: field_description(ctx.scope.file, &self.loc),
                     },
                     ftype: FType::from(self.annot.size),
                     len: RuntimeLenInfo::fixed(BitLen(*width)),
-                    validate_expr: Some(format!("value == {value}")),
+                    display: FieldDisplay::default(),
+                    validation: Some(Validation::Equals(*value)),
                     optional_field: None,
                 })
             }
             FieldDesc::FixedEnum { enum_id, tag_id } => {
                 ctx.num_fixed += 1;
-                let referenced_enum = ctx.scope.typedef[enum_id].to_dissector_info(ctx.scope);
+                let referenced_enum =
+                    ctx.scope.typedef[enum_id].to_dissector_info(ctx.scope, ctx.options);
                 let ftype = FType::from(self.annot.size);
                 Some(FieldDissectorInfo::Scalar {
                     common: CommonFieldDissectorInfo {
@@ -1153,12 +1825,15 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
                         abbr: format!("_fixed_{}", ctx.num_fixed - 1),
                         bit_offset: *bit_offset,
                         endian: ctx.scope.file.endianness.value,
-                        comments: find_comments_on_same_line(ctx.scope.file, &self.loc)
-                            .map(|comment| unwrap_comment(&comment.text).to_string()),
+                        comments: field_description(ctx.scope.file, &self.loc),
                     },
                     ftype,
                     len: referenced_enum.decl_len(),
-                    validate_expr: Some(format!(r#"{enum_id}_enum:match("{tag_id}", value)"#)),
+                    display: FieldDisplay::default(),
+                    validation: Some(Validation::EnumTag {
+                        enum_id: enum_id.clone(),
+                        tag_id: tag_id.clone(),
+                    }),
                     optional_field: None,
                 })
             }
@@ -1170,12 +1845,12 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
                         abbr: format!("_reserved_{}", ctx.num_reserved - 1),
                         bit_offset: *bit_offset,
                         endian: ctx.scope.file.endianness.value,
-                        comments: find_comments_on_same_line(ctx.scope.file, &self.loc)
-                            .map(|comment| unwrap_comment(&comment.text).to_string()),
+                        comments: field_description(ctx.scope.file, &self.loc),
                     },
                     ftype: FType(Some(BitLen(*width))),
                     len: RuntimeLenInfo::fixed(BitLen(*width)),
-                    validate_expr: None,
+                    display: FieldDisplay::default(),
+                    validation: None,
                     optional_field: None,
                 })
             }
@@ -1189,11 +1864,10 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
                 (None, Some(type_id)) => Some(FieldDissectorInfo::TypedefArray {
                     common: CommonFieldDissectorInfo {
                         display_name: id.clone(),
-                        abbr: id.clone(),
+                        abbr: ctx.abbr(id),
                         bit_offset: *bit_offset,
                         endian: ctx.scope.file.endianness.value,
-                        comments: find_comments_on_same_line(ctx.scope.file, &self.loc)
-                            .map(|comment| unwrap_comment(&comment.text).to_string()),
+                        comments: field_description(ctx.scope.file, &self.loc),
                     },
                     decl: Box::new(
                         ctx.scope
@@ -1201,10 +1875,10 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
                             .get(type_id)
                             .copied()
                             .expect("Unresolved typedef")
-                            .to_dissector_info(ctx.scope),
+                            .to_dissector_info(ctx.scope, ctx.options),
                     ),
                     array_info: ArrayFieldDissectorInfo {
-                        size_modifier: size_modifier.clone(),
+                        size_modifier: parse_size_modifier(size_modifier),
                         count: *size,
                         pad_to_size: None,
                         has_size_field: has_size_field(decl, id),
@@ -1214,36 +1888,37 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
                 (Some(width), None) => Some(FieldDissectorInfo::ScalarArray {
                     common: CommonFieldDissectorInfo {
                         display_name: id.clone(),
-                        abbr: id.clone(),
+                        abbr: ctx.abbr(id),
                         bit_offset: BitLen::default(),
                         endian: ctx.scope.file.endianness.value,
-                        comments: find_comments_on_same_line(ctx.scope.file, &self.loc)
-                            .map(|comment| unwrap_comment(&comment.text).to_string()),
+                        comments: field_description(ctx.scope.file, &self.loc),
                     },
                     array_info: ArrayFieldDissectorInfo {
                         count: *size,
-                        size_modifier: size_modifier.clone(),
+                        size_modifier: parse_size_modifier(size_modifier),
                         pad_to_size: None,
                         has_size_field: has_size_field(decl, id),
                         has_count_field: has_count_field(decl, id),
                     },
                     ftype: FType(Some(BitLen(*width))),
                     item_len: BitLen(*width),
+                    display,
+                    handoff,
                 }),
                 _ => unreachable!(),
             },
             FieldDesc::Scalar { id, width } => Some(FieldDissectorInfo::Scalar {
                 common: CommonFieldDissectorInfo {
                     display_name: String::from(id),
-                    abbr: id.into(),
+                    abbr: ctx.abbr(id),
                     bit_offset: *bit_offset,
                     endian: ctx.scope.file.endianness.value,
-                    comments: find_comments_on_same_line(ctx.scope.file, &self.loc)
-                        .map(|comment| unwrap_comment(&comment.text).to_string()),
+                    comments: field_description(ctx.scope.file, &self.loc),
                 },
                 ftype: FType(Some(BitLen(*width))),
                 len: RuntimeLenInfo::fixed(BitLen(*width)),
-                validate_expr: None,
+                display,
+                validation: None,
                 optional_field: ctx.optional_decl.get(id).cloned(),
             }),
             FieldDesc::Flag {
@@ -1252,19 +1927,19 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
                 set_value,
             } => {
                 ctx.optional_decl
-                    .insert(optional_field_id.clone(), (id.clone(), *set_value));
+                    .insert(optional_field_id.clone(), (ctx.abbr(id), *set_value));
                 Some(FieldDissectorInfo::Scalar {
                     common: CommonFieldDissectorInfo {
                         display_name: String::from(id),
-                        abbr: id.into(),
+                        abbr: ctx.abbr(id),
                         bit_offset: *bit_offset,
                         endian: ctx.scope.file.endianness.value,
-                        comments: find_comments_on_same_line(ctx.scope.file, &self.loc)
-                            .map(|comment| unwrap_comment(&comment.text).to_string()),
+                        comments: field_description(ctx.scope.file, &self.loc),
                     },
                     ftype: FType::from(self.annot.size),
                     len: RuntimeLenInfo::fixed(BitLen(1)),
-                    validate_expr: None,
+                    display,
+                    validation: None,
                     optional_field: None,
                 })
             }
@@ -1275,15 +1950,14 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
                     .get(type_id)
                     .copied()
                     .expect("Unresolved typedef")
-                    .to_dissector_info(ctx.scope);
+                    .to_dissector_info(ctx.scope, ctx.options);
                 Some(FieldDissectorInfo::Typedef {
                     common: CommonFieldDissectorInfo {
                         display_name: id.into(),
-                        abbr: id.into(),
+                        abbr: ctx.abbr(id),
                         bit_offset: *bit_offset,
                         endian: ctx.scope.file.endianness.value,
-                        comments: find_comments_on_same_line(ctx.scope.file, &self.loc)
-                            .map(|comment| unwrap_comment(&comment.text).to_string()),
+                        comments: field_description(ctx.scope.file, &self.loc),
                     },
                     decl: Box::new(dissector_info),
                     optional_field: ctx.optional_decl.get(id).cloned(),
@@ -1294,6 +1968,15 @@ impl FieldExt for Field<analyzer::ast::Annotation> {
     }
 }
 
+/// Parses the `+N` size modifier of a payload or array.
+fn parse_size_modifier(size_modifier: &Option<String>) -> Option<usize> {
+    size_modifier.as_ref().map(|size_modifier| {
+        size_modifier
+            .parse()
+            .expect("size modifier should be a number")
+    })
+}
+
 fn has_size_field(decl: &Decl<analyzer::ast::Annotation>, id: &str) -> bool {
     decl.fields().any(|field| match &field.desc {
         FieldDesc::Size { field_id, .. } => field_id == id,
@@ -1308,114 +1991,550 @@ fn has_count_field(decl: &Decl<analyzer::ast::Annotation>, id: &str) -> bool {
     })
 }
 
+/// Command line of this tool: a subcommand, or the arguments of `generate` without one.
+#[derive(clap::Parser, Debug)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub generate: Args,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum Command {
+    /// Generates the Wireshark dissector. This is the default without a subcommand.
+    Generate(Args),
+    /// Lists the packets, structs, groups and enums of a PDL file, with child packets and structs
+    /// under their parents.
+    List {
+        /// The PDL file to list the declarations of, or `-` to read it from stdin.
+        pdl_file: PathBuf,
+    },
+    /// Analyzes the PDL file without generating the dissector, reporting the constructs that
+    /// cannot be dissected. Target packets are optional.
+    Check(Args),
+    /// Prints what was understood from the PDL file as JSON, in the backend-neutral
+    /// representation of `pdl_dissector::ir`. Target packets are optional. Without a PDL file,
+    /// prints an array of the files of the dissectors declared in the project file.
+    Ir(Args),
+    /// Generates the dissector into the personal plugin folder of Wireshark, named after the
+    /// `--output` file or the PDL file.
+    Install(InstallArgs),
+    /// Removes dissectors generated by this tool from the personal plugin folder of Wireshark,
+    /// along with the modules of loaders.
+    Uninstall {
+        /// The names of the dissectors or loaders to remove, without the `.lua` extension.
+        #[arg(required = true)]
+        names: Vec<String>,
+        /// The plugin folder to remove from, instead of the personal plugin folder of Wireshark.
+        #[arg(long)]
+        plugin_dir: Option<PathBuf>,
+    },
+    /// Lists the dissectors generated by this tool in the personal plugin folder of Wireshark,
+    /// with the modules of loaders under them.
+    Installed {
+        /// The plugin folder to list, instead of the personal plugin folder of Wireshark.
+        #[arg(long)]
+        plugin_dir: Option<PathBuf>,
+    },
+}
+
+/// Arguments of the `install` subcommand.
+#[derive(clap::Args, Debug, Default)]
+pub struct InstallArgs {
+    #[command(flatten)]
+    pub args: Args,
+    /// The plugin folder to install into, instead of the personal plugin folder of Wireshark.
+    #[arg(long)]
+    pub plugin_dir: Option<PathBuf>,
+    /// Installs the dissectors as modules of a `<LOADER>.lua` plugin, which `require`s all
+    /// modules installed with the same loader. The modules are kept out of the plugin folder, so
+    /// that Wireshark only runs them through the loader.
+    #[arg(long)]
+    pub loader: Option<String>,
+}
+
 /// Command line arguments for this tool.
-#[derive(clap::Parser)]
+#[derive(clap::Parser, Debug, Default)]
 pub struct Args {
-    /// The PDL file to generate the Wireshark dissector from. See
+    /// The PDL file to generate the Wireshark dissector from, or `-` to read it from stdin. See
     /// https://github.com/google/pdl/blob/main/doc/reference.md.
-    pub pdl_file: PathBuf,
+    ///
+    /// Without a PDL file, the dissectors declared in the project file `pdl-dissector.toml` in the
+    /// current directory are generated.
+    #[arg(conflicts_with = "config")]
+    pub pdl_file: Option<PathBuf>,
     /// The type in the PDL file to generate dissector for.
     ///
     /// Since a PDL file can contain multiple packet declarations, this
     /// specifies which packet the dissector should be generated for.
     pub target_packets: Vec<String>,
+    /// The short name of the protocol, which is shown in the "Protocol" column. Wireshark derives
+    /// the display filter name of the protocol by lowercasing this name. Defaults to the name of
+    /// the target packet.
+    ///
+    /// Can only be used with a single target packet.
+    #[arg(long)]
+    pub protocol_name: Option<String>,
+    /// The descriptive name of the protocol, which is shown in the protocol tree. Defaults to the
+    /// short name of the protocol.
+    ///
+    /// Can only be used with a single target packet.
+    #[arg(long)]
+    pub protocol_full_name: Option<String>,
+    /// The prefix of the display filter names of all generated fields. Defaults to the display
+    /// filter name of the protocol, which is its lowercased short name.
+    ///
+    /// Can only be used with a single target packet.
+    #[arg(long)]
+    pub filter_prefix: Option<String>,
+    /// How the buffers given to the protocol are split into PDUs of the target packet. With `tcp`,
+    /// the length of the packet must be computable from the fields at fixed offsets in its header.
+    #[arg(long, value_enum, default_value_t)]
+    pub framing: Framing,
+    /// A TCP port to register the protocol on, so that its traffic is dissected without "Decode
+    /// As". Can be repeated.
+    ///
+    /// Can only be used with a single target packet.
+    #[arg(long = "tcp-port", value_name = "PORT")]
+    pub tcp_ports: Vec<u16>,
+    /// A UDP port to register the protocol on, like `--tcp-port`. Can be repeated.
+    ///
+    /// Can only be used with a single target packet.
+    #[arg(long = "udp-port", value_name = "PORT")]
+    pub udp_ports: Vec<u16>,
+    /// The text shown in the Info column for each PDU, e.g. `Request {opcode} for {handle}`.
+    /// `{field}` is replaced by the value of the field of the target packet with the display
+    /// filter name `field`, without the filter prefix, or by the name of its tag for enum fields.
+    /// Braces are written as `{{` and `}}`.
+    ///
+    /// Can only be used with a single target packet.
+    #[arg(long, value_name = "TEMPLATE")]
+    pub info: Option<String>,
+    /// Generates a Lua module to load with `require`, which returns a table of the protocols,
+    /// dissect functions, enums and fields, instead of a plugin that defines them as globals.
+    /// Registering the protocols is left to the plugin that loads the module, apart from the
+    /// ports given with `--tcp-port` and `--udp-port`.
+    #[arg(long)]
+    pub module: bool,
+    /// The Lua file to write the dissector to. Defaults to stdout.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    /// Instead of writing the dissector, check that the existing file is what would be
+    /// generated. Prints a unified diff and exits with status 1 if it is not.
+    #[arg(
+        long,
+        value_name = "EXISTING_LUA_FILE",
+        conflicts_with = "output",
+        requires = "pdl_file"
+    )]
+    pub check: Option<PathBuf>,
+    /// The project file declaring the dissectors to generate. Options given on the command line
+    /// override the values in the project file for all of its dissectors.
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    #[command(flatten)]
+    pub options: DissectorOptions,
+}
+
+/// Options that control how the PDL declarations are translated into Lua.
+#[derive(clap::Args, Clone, Debug, Default)]
+pub struct DissectorOptions {
+    /// How the names of declarations and fields are written in the display filter names of the
+    /// generated fields.
+    #[arg(long, value_enum, default_value_t)]
+    pub abbr_style: AbbrStyle,
+    /// Scalar fields to display as booleans, written as `<declaration>.<field>`. 1-bit fields and
+    /// flags of optional fields are always displayed as booleans.
+    #[arg(long = "boolean-field", value_name = "DECL.FIELD")]
+    pub boolean_fields: Vec<String>,
+    /// Show each byte of 8-bit arrays as a separate item, instead of a single bytes item covering
+    /// the whole array.
+    #[arg(long)]
+    pub expand_byte_arrays: bool,
+    /// Payloads and byte arrays to dissect as a packet of another PDL file, written as
+    /// `<declaration>.<field>=<file>:<packet>`. Payloads are named `_payload_` or `_body_`. The
+    /// packet gets its own protocol named after the field, generated into the same Lua file.
+    #[arg(long = "bind", value_name = "DECL.FIELD=FILE:PACKET")]
+    pub bindings: Vec<Binding>,
 }
 
-fn get_desc_id<A: Annotation>(desc: &DeclDesc<A>) -> Option<String> {
-    match desc {
-        DeclDesc::Checksum { id, .. }
-        | DeclDesc::CustomField { id, .. }
-        | DeclDesc::Enum { id, .. }
-        | DeclDesc::Packet { id, .. }
-        | DeclDesc::Struct { id, .. }
-        | DeclDesc::Group { id, .. } => Some(id.clone()),
-        DeclDesc::Test { .. } => None,
+impl DissectorOptions {
+    fn is_boolean_field(&self, decl_id: &str, field_id: &str) -> bool {
+        self.boolean_fields
+            .iter()
+            .any(|name| name.split_once('.') == Some((decl_id, field_id)))
+    }
+
+    /// Checks that the fields referenced by these options exist and can be displayed as
+    /// requested.
+    fn validate(&self, scope: &Scope<analyzer::ast::Annotation>) -> Result<(), Diagnostics> {
+        for name in &self.boolean_fields {
+            let Some((decl_id, field_id)) = name.split_once('.') else {
+                Err(Diagnostic::error().with_message(format!(
+                    "Boolean field {name:?} should be written as `<declaration>.<field>`"
+                )))?
+            };
+            let Some(decl) = scope.typedef.get(decl_id) else {
+                Err(Diagnostic::error().with_message(format!(
+                    "Unable to find declaration {decl_id:?} of boolean field {name:?}"
+                )))?
+            };
+            let Some(field) = decl.fields().find(|field| field.id() == Some(field_id)) else {
+                Err(Diagnostic::error()
+                    .with_message(format!("Unable to find field {field_id:?} in {decl_id:?}")))?
+            };
+            match &field.desc {
+                FieldDesc::Scalar { width, .. } if *width <= 64 => {}
+                _ => Err(Diagnostic::error()
+                    .with_message(format!(
+                        "Field {name:?} cannot be displayed as a boolean, only scalar fields of up to 64 bits can"
+                    ))
+                    .with_labels(vec![field.loc.primary()]))?,
+            }
+        }
+        for binding in &self.bindings {
+            let Binding {
+                decl_id, field_id, ..
+            } = binding;
+            let Some(decl) = scope.typedef.get(decl_id) else {
+                Err(Diagnostic::error().with_message(format!(
+                    "Unable to find declaration {decl_id:?} of bound field {field_id:?}"
+                )))?
+            };
+            let has_field = decl.fields().any(|field| match &field.desc {
+                FieldDesc::Payload { .. } => field_id == "_payload_",
+                FieldDesc::Body => field_id == "_body_",
+                _ => field.id() == Some(field_id),
+            });
+            if !has_field {
+                Err(Diagnostic::error()
+                    .with_message(format!("Unable to find field {field_id:?} in {decl_id:?}")))?
+            }
+        }
+        Ok(())
     }
 }
 
-fn generate_for_decl(
-    decl_name: &str,
-    decl: &Decl<pdl_compiler::analyzer::ast::Annotation>,
-    scope: &Scope<pdl_compiler::analyzer::ast::Annotation>,
+impl Args {
+    /// The project file to generate the dissectors from, if no PDL file was given.
+    pub fn project_file(&self) -> Option<PathBuf> {
+        match (&self.pdl_file, &self.config) {
+            (_, Some(config)) => Some(config.clone()),
+            (None, None) => Some(PathBuf::from(config::PROJECT_FILE_NAME)),
+            (Some(_), None) => None,
+        }
+    }
+}
+
+/// Generates the dissector for `args` into `writer`. Returns the warnings found in the PDL file,
+/// which don't stop the dissector from being generated.
+pub fn run(
+    args: Args,
+    sources: &mut SourceDatabase,
     writer: &mut impl std::io::Write,
-) -> std::io::Result<()> {
-    let target_dissector_info = decl.to_dissector_info(scope);
+) -> Result<Diagnostics, Diagnostics> {
+    Generator::from_args(args)?.generate(sources, writer)
+}
 
-    writedoc!(
-        writer,
-        r#"
-        -- Protocol definition for "{decl_name}"
-        {decl_name}_protocol = Proto("{decl_name}",  "{decl_name}")
-        "#,
-    )?;
+/// Generates the dissector for `args` into its `output` file, or into `writer` if it has none. The
+/// output file is only written if the dissector is generated without errors.
+pub fn run_to_output(
+    args: Args,
+    sources: &mut SourceDatabase,
+    writer: &mut impl std::io::Write,
+) -> Result<Diagnostics, Diagnostics> {
+    let Some(output) = args.output.clone() else {
+        return run(args, sources, writer);
+    };
+    let mut dissector = vec![];
+    let warnings = run(args, sources, &mut dissector)?;
+    std::fs::write(&output, dissector).map_err(|err| {
+        Diagnostic::error().with_message(format!("Unable to write {}: {err}", output.display()))
+    })?;
+    Ok(warnings)
+}
 
-    target_dissector_info.write_main_dissector(writer)?;
-    Ok(())
+/// Checks that the file at `path` is the dissector that would be generated for `args`. If it is
+/// not, a unified diff from the file to the generated dissector is written into `writer`. Returns
+/// whether the file is up to date, and the warnings found in the PDL file.
+pub fn run_check(
+    args: Args,
+    path: &Path,
+    sources: &mut SourceDatabase,
+    writer: &mut impl std::io::Write,
+) -> Result<(bool, Diagnostics), Diagnostics> {
+    let existing = std::fs::read_to_string(path).map_err(|err| {
+        Diagnostic::error().with_message(format!("Unable to read {}: {err}", path.display()))
+    })?;
+    let mut generated = vec![];
+    let warnings = run(args, sources, &mut generated)?;
+    match check::diff(path, &existing, &String::from_utf8_lossy(&generated)) {
+        Some(diff) => {
+            write!(writer, "{diff}")?;
+            Ok((false, warnings))
+        }
+        None => Ok((true, warnings)),
+    }
 }
 
-pub fn run(
+/// Generates the dissectors declared in the project file at `path`, with the options given on the
+/// command line in `cli` and `matches` taking precedence. Dissectors without an `output` are
+/// written into `writer`. Stops at the first dissector that fails to generate.
+pub fn run_project(
+    path: &Path,
+    cli: &Args,
+    matches: &clap::ArgMatches,
+    sources: &mut SourceDatabase,
+    writer: &mut impl std::io::Write,
+) -> Result<Diagnostics, Diagnostics> {
+    let mut warnings = vec![];
+    for args in project_args(path, cli, matches)? {
+        warnings.extend(run_to_output(args, sources, writer)?.into_vec());
+    }
+    Ok(Diagnostics::from(warnings))
+}
+
+/// The arguments of the dissectors declared in the project file at `path`, with the options given
+/// on the command line in `cli` and `matches` taking precedence.
+fn project_args(
+    path: &Path,
+    cli: &Args,
+    matches: &clap::ArgMatches,
+) -> Result<Vec<Args>, Diagnostics> {
+    let project = config::Project::load(path)?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    project.to_args(base_dir, cli, matches)
+}
+
+/// The arguments of the dissectors to run a subcommand for: `args` itself, or the dissectors of
+/// its project file if it has no PDL file.
+fn command_args(args: Args, matches: &clap::ArgMatches) -> Result<Vec<Args>, Diagnostics> {
+    match args.project_file() {
+        Some(project_file) => project_args(&project_file, &args, matches),
+        None => Ok(vec![args]),
+    }
+}
+
+/// Writes the declarations of the PDL file at `pdl_file` into `writer`, with child packets and
+/// structs under their parents.
+pub fn run_list(
+    pdl_file: PathBuf,
+    sources: &mut SourceDatabase,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Diagnostics> {
+    let args = Args {
+        pdl_file: Some(pdl_file),
+        ..Default::default()
+    };
+    Generator::from_args(args)?.list(sources, writer)
+}
+
+/// Analyzes the PDL file of `args` and checks its options without generating the dissector.
+/// Returns the warnings found in the PDL file.
+pub fn run_analysis(args: Args, sources: &mut SourceDatabase) -> Result<Diagnostics, Diagnostics> {
+    Generator::from_args(args)?.check(sources)
+}
+
+/// Writes the backend-neutral representation of the PDL file of `args` into `writer` as JSON.
+pub fn run_ir(
     args: Args,
     sources: &mut SourceDatabase,
     writer: &mut impl std::io::Write,
 ) -> Result<(), Diagnostics> {
-    let _ = env_logger::try_init();
-
-    let file = pdl_compiler::parser::parse_file(
-        sources,
-        args.pdl_file
-            .to_str()
-            .expect("pdl_file path should be a valid string"),
-    )?;
-    let analyzed_file = analyzer::analyze(&file)?;
-    let scope = Scope::new(&analyzed_file)?;
-    if args.target_packets.is_empty() {
-        Err(Diagnostic::error().with_message("Target packet must be specified"))?
-    }
-
-    write!(writer, "{}", include_str!("utils.lua"))?;
-    for target_packet in args.target_packets {
-        for decl in analyzed_file.declarations.iter() {
-            let decl_dissector_info = decl.to_dissector_info(&scope);
-            decl_dissector_info.write_proto_fields(writer)?;
-            decl_dissector_info.write_dissect_fn(writer)?;
+    let file = Generator::from_args(args)?.ir(sources)?;
+    write_ir_json(&file, writer)
+}
+
+fn write_ir_json(
+    value: &impl serde::Serialize,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Diagnostics> {
+    serde_json::to_writer_pretty(&mut *writer, value).map_err(|err| {
+        Diagnostic::error().with_message(format!("Unable to write the IR as JSON: {err}"))
+    })?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Generates the dissector for `args` into `plugin_dir`, named by [`install::plugin_file_name`].
+/// Files that were not generated by this tool are not overwritten. Returns the path of the
+/// installed dissector, and the warnings found in the PDL file.
+pub fn run_install(
+    mut args: Args,
+    plugin_dir: &Path,
+    sources: &mut SourceDatabase,
+) -> Result<(PathBuf, Diagnostics), Diagnostics> {
+    let path = plugin_dir.join(install::plugin_file_name(&args)?);
+    install::check_overwrite(&path)?;
+    std::fs::create_dir_all(plugin_dir).map_err(|err| {
+        Diagnostic::error()
+            .with_message(format!("Unable to create {}: {err}", plugin_dir.display()))
+    })?;
+    args.output = Some(path.clone());
+    let warnings = run_to_output(args, sources, &mut std::io::sink())?;
+    Ok((path, warnings))
+}
+
+/// Runs the subcommand of `cli`, whose command line was parsed into `matches`, writing its output
+/// into `writer`. Returns whether the result is positive, which is false when a `--check`ed
+/// dissector is outdated or `check` finds errors in the PDL files, and the diagnostics to report.
+/// Errors that stop the subcommand from running are returned as `Err`.
+pub fn run_cli(
+    cli: Cli,
+    matches: &clap::ArgMatches,
+    sources: &mut SourceDatabase,
+    writer: &mut impl std::io::Write,
+) -> Result<(bool, Diagnostics), Diagnostics> {
+    let (command, matches) = match (cli.command, matches.subcommand()) {
+        (Some(command), Some((_, sub_matches))) => (command, sub_matches),
+        (_, _) => (Command::Generate(cli.generate), matches),
+    };
+    match command {
+        Command::Generate(args) => match args.check.clone() {
+            Some(existing) => run_check(args, &existing, sources, writer),
+            None => match args.project_file() {
+                Some(project_file) => run_project(&project_file, &args, matches, sources, writer),
+                None => run_to_output(args, sources, writer),
+            }
+            .map(|warnings| (true, warnings)),
+        },
+        Command::List { pdl_file } => {
+            run_list(pdl_file, sources, writer)?;
+            Ok((true, Diagnostics::from(vec![])))
         }
-        if target_packet == "_all_" {
-            for decl in analyzed_file.declarations.iter() {
-                if matches!(decl.desc, DeclDesc::Packet { .. }) {
-                    generate_for_decl(decl.id().unwrap(), decl, &scope, writer)?;
+        Command::Check(args) => {
+            // The errors found in the PDL files are the result of the check, while missing files
+            // stop it like other errors.
+            let mut succeeded = true;
+            let mut diagnostics = vec![];
+            for args in command_args(args, matches)? {
+                if let Some(pdl_file) = args
+                    .pdl_file
+                    .as_ref()
+                    .filter(|path| *path != Path::new("-"))
+                {
+                    if !pdl_file.is_file() {
+                        Err(Diagnostic::error()
+                            .with_message(format!("Unable to find {}", pdl_file.display())))?
+                    }
+                }
+                match run_analysis(args, sources) {
+                    Ok(warnings) => diagnostics.extend(warnings.into_vec()),
+                    Err(errors) => {
+                        succeeded = false;
+                        diagnostics.extend(errors.into_vec());
+                    }
                 }
             }
-        } else {
-            let target_decl = analyzed_file.declarations.iter().find(|decl| {
-                get_desc_id(&decl.desc)
-                    .map(|id| id == target_packet)
-                    .unwrap_or(false)
-            });
-            if let Some(decl) = target_decl {
-                generate_for_decl(&target_packet, decl, &scope, writer)?;
-            } else {
-                Err(Diagnostic::error()
-                    .with_message(format!("Unable to find declaration {target_packet:?}")))?;
+            Ok((succeeded, Diagnostics::from(diagnostics)))
+        }
+        Command::Ir(args) => {
+            match args.project_file() {
+                // The dissectors of a project file are printed as an array, one file each.
+                Some(_) => {
+                    let files = command_args(args, matches)?
+                        .into_iter()
+                        .map(|args| Generator::from_args(args)?.ir(sources))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    write_ir_json(&files, writer)?;
+                }
+                None => run_ir(args, sources, writer)?,
             }
+            Ok((true, Diagnostics::from(vec![])))
+        }
+        Command::Install(InstallArgs {
+            args,
+            plugin_dir,
+            loader,
+        }) => {
+            let plugin_dir = install::resolve_plugin_dir(plugin_dir)?;
+            let all_args = command_args(args, matches)?;
+            // The names are checked before anything is written, so that no modules are left
+            // without a loader.
+            let install_dir = match &loader {
+                Some(loader) => {
+                    install::check_loader_name(loader)?;
+                    for args in &all_args {
+                        install::module_file_name(args)?;
+                    }
+                    install::modules_dir(&plugin_dir, loader)
+                }
+                None => plugin_dir.clone(),
+            };
+            let mut warnings = vec![];
+            for args in all_args {
+                let (path, install_warnings) = run_install(args, &install_dir, sources)?;
+                writeln!(writer, "Installed {}", path.display())?;
+                warnings.extend(install_warnings.into_vec());
+            }
+            if let Some(loader) = loader {
+                let path = install::write_loader(&plugin_dir, &loader)?;
+                writeln!(writer, "Installed {}", path.display())?;
+            }
+            Ok((true, Diagnostics::from(warnings)))
+        }
+        Command::Uninstall { names, plugin_dir } => {
+            let plugin_dir = install::resolve_plugin_dir(plugin_dir)?;
+            for name in names {
+                for path in install::uninstall(&plugin_dir, &name)? {
+                    writeln!(writer, "Removed {}", path.display())?;
+                }
+            }
+            Ok((true, Diagnostics::from(vec![])))
+        }
+        Command::Installed { plugin_dir } => {
+            let plugin_dir = install::resolve_plugin_dir(plugin_dir)?;
+            for plugin in install::installed_plugins(&plugin_dir)? {
+                writeln!(
+                    writer,
+                    "{} {} {}",
+                    plugin.name,
+                    plugin.version,
+                    plugin.path.display()
+                )?;
+                for module in plugin.modules {
+                    writeln!(
+                        writer,
+                        "    {} {} {}",
+                        module.name,
+                        module.version,
+                        module.path.display()
+                    )?;
+                }
+            }
+            Ok((true, Diagnostics::from(vec![])))
         }
     }
-    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{io::BufWriter, path::PathBuf};
+    use std::{
+        io::{BufWriter, Write},
+        path::PathBuf,
+    };
 
+    use clap::{CommandFactory as _, FromArgMatches as _};
+    use indoc::indoc;
+    use mlua::TableExt as _;
     use pdl_compiler::ast::SourceDatabase;
 
-    use crate::{fakes::wireshark_lua, run, Args};
+    use crate::{
+        diagnostics::Diagnostics, fakes::wireshark_lua, run, run_analysis, run_check, run_cli,
+        AbbrStyle, Args, Binding, Cli, DissectorOptions, Framing, Generator,
+    };
 
     #[test]
     fn test_bluetooth_hci() -> anyhow::Result<()> {
         let args = Args {
-            pdl_file: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-                .join("tests/compilation_test/bluetooth_hci.pdl"),
+            pdl_file: Some(
+                PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                    .join("tests/compilation_test/bluetooth_hci.pdl"),
+            ),
             target_packets: vec!["_all_".into()],
+            ..Default::default()
         };
         let lua = wireshark_lua()?;
         lua.load(run_with_args(args)).exec()?;
@@ -1426,15 +2545,599 @@ mod tests {
     fn test_le_test_file() -> anyhow::Result<()> {
         // Copied from pdl-compiler/tests/canonical/le_test_file.pdl
         let args = Args {
-            pdl_file: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-                .join("tests/compilation_test/le_test_file.pdl"),
+            pdl_file: Some(
+                PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                    .join("tests/compilation_test/le_test_file.pdl"),
+            ),
             target_packets: vec!["_all_".into()],
+            ..Default::default()
         };
         let lua = wireshark_lua()?;
         lua.load(run_with_args(args)).exec()?;
         Ok(())
     }
 
+    #[test]
+    fn test_same_struct_twice() -> anyhow::Result<()> {
+        let lua = load_dissector(
+            indoc! {r#"
+                little_endian_packets
+                struct Address {
+                    x: 8,
+                }
+                packet Route {
+                    src: Address,
+                    dst: Address,
+                }
+            "#},
+            Args {
+                target_packets: vec!["Route".into()],
+                ..Default::default()
+            },
+        )?;
+        let tree = dissect(&lua, "Route", &[1, 2])?;
+        assert_eq!(field_value::<u32>(&tree, "route.src.x")?, 1);
+        assert_eq!(field_value::<u32>(&tree, "route.dst.x")?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_child_packet_fields() -> anyhow::Result<()> {
+        let lua = load_dissector(
+            indoc! {r#"
+                little_endian_packets
+                packet Parent {
+                    kind: 8,
+                    _body_,
+                }
+                packet Child : Parent (kind = 1) {
+                    value: 8,
+                }
+                packet Leaf : Parent (kind = 2) {
+                    _body_,
+                }
+                packet GrandChild : Leaf {
+                    value: 8,
+                }
+            "#},
+            Args {
+                target_packets: vec!["Parent".into()],
+                ..Default::default()
+            },
+        )?;
+        let tree = dissect(&lua, "Parent", &[2, 7])?;
+        assert_eq!(
+            dump(&tree)?,
+            indoc! {"
+                Parent
+                  kind
+                  Leaf
+                    GrandChild
+                      value
+                  Leaf.GrandChild"}
+        );
+        assert_eq!(
+            field_value::<u32>(&tree, "parent.Leaf.GrandChild.value")?,
+            7
+        );
+        assert_eq!(
+            field_value::<String>(&tree, "parent._packet_chain_")?,
+            "Leaf.GrandChild"
+        );
+
+        // Fields of the packet cannot clash with the generated fields.
+        let lua = load_dissector(
+            indoc! {r#"
+                little_endian_packets
+                packet Top {
+                    packet: 8,
+                    packet_chain: 8,
+                    _body_,
+                }
+                packet Child : Top (packet = 1) {
+                    value: 8,
+                }
+            "#},
+            Args {
+                target_packets: vec!["Top".into()],
+                ..Default::default()
+            },
+        )?;
+        let tree = dissect(&lua, "Top", &[1, 2, 3])?;
+        assert_eq!(field_value::<u32>(&tree, "top.packet")?, 1);
+        assert_eq!(field_value::<u32>(&tree, "top.packet_chain")?, 2);
+        assert_eq!(field_value::<u32>(&tree, "top.Child.value")?, 3);
+        assert_eq!(field_value::<String>(&tree, "top._packet_chain_")?, "Child");
+        for (abbr, ftype) in [
+            ("top.packet", "UINT8"),
+            ("top.packet_chain", "UINT8"),
+            ("top._packet_", "STRING"),
+            ("top._packet_chain_", "STRING"),
+        ] {
+            let field = registered_field(&lua, "Top", abbr)?;
+            assert_eq!(field.get::<_, String>("type_name")?, ftype, "{abbr}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_boolean_fields() -> anyhow::Result<()> {
+        let pdl = indoc! {r#"
+            little_endian_packets
+            packet Flags {
+                enabled: 8,
+                mode: 1,
+                _reserved_: 7,
+            }
+        "#};
+        let lua = load_dissector(
+            pdl,
+            Args {
+                target_packets: vec!["Flags".into()],
+                options: DissectorOptions {
+                    boolean_fields: vec!["Flags.enabled".into()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )?;
+        for abbr in ["flags.enabled", "flags.mode"] {
+            let field = registered_field(&lua, "Flags", abbr)?;
+            assert_eq!(field.get::<_, String>("type_name")?, "BOOLEAN", "{abbr}");
+        }
+        let tree = dissect(&lua, "Flags", &[1, 0x80])?;
+        assert_eq!(field_value::<u32>(&tree, "flags.enabled")?, 1);
+        assert_eq!(
+            dump(&tree)?,
+            indoc! {"
+                Flags
+                  enabled
+                  1... .... = mode: Set
+                  .000 0000 = Reserved: 0"}
+        );
+
+        assert!(Generator::from_source("flags.pdl", pdl)
+            .target("Flags")
+            .boolean_field("Flags", "missing")
+            .generate(&mut SourceDatabase::new(), &mut vec![])
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_display_annotations() -> anyhow::Result<()> {
+        let lua = load_dissector(
+            indoc! {r#"
+                big_endian_packets
+                packet Annotated {
+                    src: 32, // Source address @display=ipv4
+                    delay: 16, // @unit=ms
+                    flags: 4, // @base=hex
+                    mode: 4, // @base=dec_hex
+                    _size_(name): 8,
+                    name: 8[], // @display=string
+                    mac: 8[6], // @display=ether
+                }
+            "#},
+            Args {
+                target_packets: vec!["Annotated".into()],
+                ..Default::default()
+            },
+        )?;
+        let field = |abbr| registered_field(&lua, "Annotated", abbr);
+        let base = |name| lua.load(format!("base.{name}")).eval::<u32>();
+        assert_eq!(
+            field("annotated.src")?.get::<_, String>("type_name")?,
+            "IPv4"
+        );
+        assert_eq!(
+            field("annotated.src")?.get::<_, String>("description")?,
+            "Source address"
+        );
+        let delay = field("annotated.delay")?;
+        assert_eq!(delay.get::<_, Vec<String>>("valuestring")?, [" ms"]);
+        assert_eq!(
+            delay.get::<_, u32>("base")?,
+            base("DEC")? + base("UNIT_STRING")?
+        );
+        assert_eq!(
+            field("annotated.flags")?.get::<_, u32>("base")?,
+            base("HEX")?
+        );
+        assert_eq!(
+            field("annotated.mode")?.get::<_, u32>("base")?,
+            base("DEC_HEX")?
+        );
+        assert_eq!(
+            field("annotated.name")?.get::<_, String>("type_name")?,
+            "STRING"
+        );
+        assert_eq!(
+            field("annotated.mac")?.get::<_, String>("type_name")?,
+            "ETHER"
+        );
+        let mut bytes = vec![192, 168, 0, 1, 0, 20, 0x1f, 3];
+        bytes.extend(b"abc");
+        bytes.extend([0xaa; 6]);
+        let tree = dissect(&lua, "Annotated", &bytes)?;
+        let dump = dump(&tree)?;
+        assert!(dump.contains("0001 .... = flags: 0x1"), "{dump}");
+        assert!(dump.contains(".... 1111 = mode: 15 (0xf)"), "{dump}");
+        assert_eq!(field_value::<u32>(&tree, "annotated.src")?, 0xc0a80001);
+        assert_eq!(field_value::<u32>(&tree, "annotated.delay")?, 20);
+        assert_eq!(field_value::<String>(&tree, "annotated.name")?, "abc");
+        assert_eq!(
+            field_value::<mlua::String>(&tree, "annotated.mac")?,
+            [0xaa; 6][..]
+        );
+
+        // Addresses are in network byte order, even in little endian files.
+        let lua = load_dissector(
+            indoc! {r#"
+                little_endian_packets
+                packet Annotated {
+                    src: 32, // @display=ipv4
+                    mac: 8[6], // @display=ether
+                    value: 16,
+                }
+            "#},
+            Args {
+                target_packets: vec!["Annotated".into()],
+                ..Default::default()
+            },
+        )?;
+        let tree = dissect(
+            &lua,
+            "Annotated",
+            &[10, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0x34, 0x12],
+        )?;
+        assert_eq!(field_value::<u32>(&tree, "annotated.src")?, 0x0a000001);
+        assert_eq!(field_value::<u32>(&tree, "annotated.value")?, 0x1234);
+        Ok(())
+    }
+
+    #[test]
+    fn test_typedef_arrays() -> anyhow::Result<()> {
+        let lua = load_dissector(
+            indoc! {r#"
+                little_endian_packets
+                enum Kind: 8 {
+                    A = 1,
+                    B = 2,
+                }
+                struct Item {
+                    a: 8,
+                    b: 16,
+                }
+                packet Arrays {
+                    _count_(items): 8,
+                    items: Item[],
+                    kinds: Kind[2],
+                }
+            "#},
+            Args {
+                target_packets: vec!["Arrays".into()],
+                ..Default::default()
+            },
+        )?;
+        let tree = dissect(&lua, "Arrays", &[2, 1, 0x34, 0x12, 2, 0, 0, 2, 1])?;
+        // Lengths are shown without the fraction of Lua 5.3+ floats, e.g. `6 bytes`, not `6.0 bytes`.
+        assert_eq!(
+            dump(&tree)?,
+            indoc! {"
+                Arrays
+                  Count(items)
+                  items (2 items, 6 bytes)
+                    items[0]
+                      a
+                      b
+                    items[1]
+                      a
+                      b
+                    Item count
+                  kinds (2 items, 2 bytes)
+                    [0] kinds
+                    [1] kinds
+                    Item count"}
+        );
+        assert_eq!(field_value::<u32>(&tree, "arrays.items.b")?, 0x1234);
+        assert_eq!(field_value::<u32>(&tree, "arrays.items_item_count")?, 2);
+        assert_eq!(field_value::<u32>(&tree, "arrays.kinds_item_count")?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_loop_guards() -> anyhow::Result<()> {
+        let lua = load_dissector(
+            indoc! {r#"
+                little_endian_packets
+                struct Empty {}
+                packet Arrays {
+                    _count_(counted): 32,
+                    counted: Empty[],
+                    rest: Empty[],
+                }
+            "#},
+            Args {
+                target_packets: vec!["Arrays".into()],
+                ..Default::default()
+            },
+        )?;
+        // A count of 0xffffffff items of length 0, followed by a byte that `rest` makes no
+        // progress on.
+        lua.globals().set("ARRAY_MAX_COUNT", 16)?;
+        let dump = dump(&dissect(&lua, "Arrays", &[0xff, 0xff, 0xff, 0xff, 0x01])?)?;
+        assert!(dump.contains("expert: Error: Stopped dissecting `counted` after 16 items"));
+        assert!(dump.contains("counted (16 items, "));
+        assert!(dump.contains("expert: Error: Stopped dissecting `rest` at an item of length 0"));
+        assert!(dump.contains("rest (1 item, "));
+        Ok(())
+    }
+
+    #[test]
+    fn test_consumed_len_checks() -> anyhow::Result<()> {
+        let lua = load_dissector(
+            indoc! {r#"
+                little_endian_packets
+                struct Item {
+                    a: 8,
+                    b: 16,
+                }
+                packet Parent {
+                    _size_(items): 8,
+                    items: Item[+2],
+                    _size_(_body_): 8,
+                    _body_,
+                }
+                packet Child: Parent {
+                    c: 8,
+                }
+            "#},
+            Args {
+                target_packets: vec!["Parent".into()],
+                ..Default::default()
+            },
+        )?;
+        // The body is declared 2 bytes long, but `Child` only consumes 1.
+        let dump_of = |bytes| dump(&dissect(&lua, "Parent", bytes)?);
+        assert_eq!(
+            dump_of(&[1, 1, 2, 3, 2, 4, 5])?,
+            indoc! {"
+                Parent
+                  Size(items)
+                  items (1 item, 3 bytes)
+                    items[0]
+                      a
+                      b
+                    Item count
+                  Size(_body_)
+                  Child
+                    c
+                  expert: Error: Size(Body)=2 but 1 bytes consumed
+                  Undecoded (1 bytes)
+                  Child"}
+        );
+        // The items are declared 4 bytes long, which is not a whole number of items.
+        let dump = dump_of(&[2, 1, 2, 3, 4, 1, 4])?;
+        assert!(
+            dump.contains("items (2 items, 6 bytes)")
+                && dump.contains("expert: Error: Size(items)=4 but 6 bytes consumed"),
+            "{dump}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_indistinguishable_children() -> anyhow::Result<()> {
+        let pdl = indoc! {r#"
+            little_endian_packets
+            packet Parent {
+                type: 8,
+                flag: 8,
+                _body_,
+            }
+            packet A: Parent (type = 1) {}
+            packet B: Parent (type = 1, flag = 2) {}
+            packet C: Parent (type = 2) {}
+        "#};
+        let warnings = Generator::from_source("parent.pdl", pdl)
+            .target("Parent")
+            .generate(&mut SourceDatabase::new(), &mut vec![])?
+            .to_string();
+        assert!(warnings.contains("`B` cannot be told apart from `A` by its constraints"));
+        assert!(!warnings.contains("`C` cannot be told apart"));
+        let lua = load_dissector(
+            pdl,
+            Args {
+                target_packets: vec!["Parent".into()],
+                ..Default::default()
+            },
+        )?;
+        let dump = dump(&dissect(&lua, "Parent", &[3, 2])?)?;
+        assert!(
+            dump.contains("expert: type=3, flag=2 matched no child of Parent (expected A: type=1; B: type=1, flag=2; C: type=2)"),
+            "{dump}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_byte_arrays() -> anyhow::Result<()> {
+        let pdl = indoc! {r#"
+            little_endian_packets
+            packet Arrays {
+                _count_(counted): 8,
+                counted: 8[],
+                wide: 16[2],
+            }
+        "#};
+        let bytes = [2, 0xaa, 0xbb, 1, 0, 2, 0];
+        let lua = load_dissector(
+            pdl,
+            Args {
+                target_packets: vec!["Arrays".into()],
+                ..Default::default()
+            },
+        )?;
+        let field = registered_field(&lua, "Arrays", "arrays.counted")?;
+        assert_eq!(field.get::<_, String>("type_name")?, "BYTES");
+        let tree = dissect(&lua, "Arrays", &bytes)?;
+        assert_eq!(
+            field_value::<mlua::String>(&tree, "arrays.counted")?,
+            [0xaa, 0xbb][..]
+        );
+        // Only 8-bit arrays are shown as a single item.
+        assert_eq!(
+            dump(&tree)?,
+            indoc! {"
+                Arrays
+                  Count(counted)
+                  counted
+                  [0] wide
+                  [1] wide"}
+        );
+
+        let lua = load_dissector(
+            pdl,
+            Args {
+                target_packets: vec!["Arrays".into()],
+                options: DissectorOptions {
+                    expand_byte_arrays: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )?;
+        let field = registered_field(&lua, "Arrays", "arrays.counted")?;
+        assert_eq!(field.get::<_, String>("type_name")?, "UINT8");
+        assert_eq!(
+            dump(&dissect(&lua, "Arrays", &bytes)?)?,
+            indoc! {"
+                Arrays
+                  Count(counted)
+                  [0] counted
+                  [1] counted
+                  [0] wide
+                  [1] wide"}
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_display_annotations() -> anyhow::Result<()> {
+        for (field, message) in [
+            ("a: 16, // @display=ipv4", "must be 4 bytes"),
+            ("a: 8, // @display=hex", "Unknown display type `hex`"),
+            ("a: 8, // @colour=red", "Unknown annotation `@colour`"),
+            (
+                "a: 8[], // @base=hex",
+                "can only be used on fields displayed as integers",
+            ),
+            (
+                "a: 8, // @display=string",
+                "Only byte arrays can be displayed as strings",
+            ),
+            (
+                "_reserved_: 4,\n    a: 32, // @display=ipv4\n    _reserved_: 4,",
+                "byte boundary",
+            ),
+        ] {
+            let source = format!("big_endian_packets\npacket Annotated {{\n    {field}\n}}\n");
+            let diagnostics = Generator::from_source("annotated.pdl", source)
+                .target("Annotated")
+                .generate(&mut SourceDatabase::new(), &mut vec![])
+                .unwrap_err();
+            assert!(
+                format!("{diagnostics:?}").contains(message),
+                "Expected {message:?} in {diagnostics:?}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_protocol_names() -> anyhow::Result<()> {
+        let pdl = include_str!("../tests/test_le.pdl");
+        let simple_packet = [0, 1, 0, 0, 0, 0, 0, 0, 0];
+        let lua = load_dissector(
+            pdl,
+            Args {
+                target_packets: vec!["TopLevel".into()],
+                protocol_name: Some("Coffee".into()),
+                protocol_full_name: Some("Coffee Protocol".into()),
+                options: DissectorOptions {
+                    abbr_style: AbbrStyle::SnakeCase,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )?;
+        let protocol: mlua::Table = lua.globals().get("TopLevel_protocol")?;
+        assert_eq!(protocol.get::<_, String>("name")?, "Coffee");
+        assert_eq!(protocol.get::<_, String>("description")?, "Coffee Protocol");
+        let tree = dissect(&lua, "TopLevel", &simple_packet)?;
+        let columns: mlua::Table = tree.get::<_, mlua::Table>("pinfo")?.get("cols")?;
+        assert_eq!(columns.get::<_, String>("protocol")?, "Coffee");
+        assert_eq!(
+            field_value::<u32>(&tree, "coffee.simple_packet.scalar_value")?,
+            1
+        );
+
+        // The fields are under the filter name of the protocol, whatever the abbreviation style.
+        let lua = load_dissector(
+            pdl,
+            Args {
+                target_packets: vec!["TopLevel".into()],
+                ..Default::default()
+            },
+        )?;
+        let tree = dissect(&lua, "TopLevel", &simple_packet)?;
+        assert_eq!(
+            field_value::<u32>(&tree, "toplevel.SimplePacket.scalar_value")?,
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_expert_fields() -> anyhow::Result<()> {
+        let lua = load_dissector(
+            include_str!("../tests/test_le.pdl"),
+            Args {
+                target_packets: vec!["TopLevel".into()],
+                filter_prefix: Some("coffee".into()),
+                ..Default::default()
+            },
+        )?;
+        let experts: mlua::Table = lua
+            .globals()
+            .get::<_, mlua::Table>("TopLevel_protocol")?
+            .get("experts")?;
+        let unknown_enum: mlua::Table = experts.get("unknown_enum")?;
+        assert_eq!(
+            unknown_enum.get::<_, String>("abbr")?,
+            "coffee.expert.unknown_enum"
+        );
+        let dump = dump(&dissect(&lua, "TopLevel", &[99])?)?;
+        assert!(
+            dump.contains("\n  expert: Unknown enum value: 99\n"),
+            "{dump}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_protocol_names_with_multiple_targets() {
+        let args = Args {
+            pdl_file: Some(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/test_le.pdl")),
+            target_packets: vec!["_all_".into()],
+            filter_prefix: Some("coffee".into()),
+            ..Default::default()
+        };
+        let mut writer = BufWriter::new(Vec::new());
+        assert!(run(args, &mut SourceDatabase::new(), &mut writer).is_err());
+    }
+
     #[test]
     fn test_format_bitstring() -> anyhow::Result<()> {
         let lua = wireshark_lua()?;
@@ -1451,7 +3154,930 @@ mod tests {
         Ok(())
     }
 
-    fn run_with_args(args: Args) -> Vec<u8> {
+    #[test]
+    fn test_report_dissect_error() -> anyhow::Result<()> {
+        let lua = wireshark_lua()?;
+        lua.load(include_str!("utils.lua")).exec()?;
+        lua.load(mlua::chunk! {
+            local reported = {}
+            local tree = {
+                add = function(self, range, label)
+                    reported.label = label
+                    return {
+                        add_proto_expert_info = function(self, expert, text)
+                            reported.text = text
+                        end,
+                    }
+                end,
+            }
+            local experts = { dissect_error = {} }
+            assert(report_dissect_error(tree, experts, Tvb("abcd"), 2, "Rec", "b", "oops") == 4)
+            assert(reported.label == "[Malformed]")
+            assert(reported.text == "Error: Failed to dissect `Rec.b`: oops", reported.text)
+        })
+        .exec()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncated_packets() -> anyhow::Result<()> {
+        let lua = wireshark_lua()?;
+        lua.load(include_str!("utils.lua")).exec()?;
+        lua.load(mlua::chunk! {
+            local truncated = Tvb("abcdef", 10)
+            local full = Tvb("abcdef")
+            // Fields running past the captured bytes of a truncated buffer raise `PACKET_TRUNCATED`...
+            local ok, err = pcall(enforce_len_limit, 8, truncated(2), Tree(), {})
+            assert(not ok and err == PACKET_TRUNCATED)
+            ok, err = pcall(check_truncated, truncated, 6, nil)
+            assert(not ok and err == PACKET_TRUNCATED)
+            // ...unless they also run past the reported length.
+            assert(enforce_len_limit(9, truncated(2), Tree(), { length_overrun = {} }) == 4)
+            assert(enforce_len_limit(8, full(2), Tree(), { length_overrun = {} }) == 4)
+            check_truncated(full, 6, nil)
+            // Ranges with a length are fully captured, even in a truncated buffer.
+            assert(enforce_len_limit(8, truncated(2, 4), Tree(), { length_overrun = {} }) == 4)
+            check_truncated(truncated(2, 4), 4, nil)
+            // Other errors are not reported as truncation.
+            ok, err = pcall(report_dissect_error, Tree(), {}, Tvb("abcd"), 2, "Rec", "b", PACKET_TRUNCATED)
+            assert(not ok and err == PACKET_TRUNCATED)
+            ok, err = pcall(report_truncated, Tree(), Tvb("abcd"), "Rec", "oops")
+            assert(not ok and err == "oops")
+        })
+        .exec()?;
+
+        let lua = load_dissector(
+            indoc! {r#"
+                little_endian_packets
+                struct Item {
+                    value: 8,
+                }
+                packet Top {
+                    _size_(_body_): 8,
+                    _body_,
+                    tail: Item[4],
+                }
+                packet Inner : Top {
+                    _count_(items): 8,
+                    items: Item[],
+                }
+                packet Open {
+                    _body_,
+                }
+                packet OpenInner : Open {
+                    _count_(items): 8,
+                    items: Item[],
+                }
+            "#},
+            Args {
+                target_packets: vec!["Top".into(), "Open".into()],
+                ..Default::default()
+            },
+        )?;
+        // Only 5 of the 8 bytes of the frame were captured. The count of `items` is wrong, which is
+        // malformed even in a truncated frame, while `tail` is cut off by the capture.
+        let dump: String = lua
+            .load(mlua::chunk! {
+                local buffer = Tvb(string.char(3, 5, 1, 2, 0xaa), 8)
+                local pinfo = new_pinfo(buffer:len())
+                pinfo.len = 8
+                local tree = Tree()
+                Top_protocol.dissector(buffer, pinfo, tree)
+                return tree:dump()
+            })
+            .eval()?;
+        assert!(
+            dump.contains("expert: Error: Expected 5 `items` items but only found 2"),
+            "{dump}"
+        );
+        assert!(
+            dump.contains("[Packet size limited during capture: Top truncated]"),
+            "{dump}"
+        );
+        // Without a size, the body extends to the end of the frame, so its items were cut off.
+        let dump: String = lua
+            .load(mlua::chunk! {
+                local buffer = Tvb(string.char(5, 1, 2), 6)
+                local pinfo = new_pinfo(buffer:len())
+                pinfo.len = 6
+                local tree = Tree()
+                Open_protocol.dissector(buffer, pinfo, tree)
+                return tree:dump()
+            })
+            .eval()?;
+        assert!(
+            dump.contains("[Packet size limited during capture: Open truncated]"),
+            "{dump}"
+        );
+        assert!(!dump.contains("only found"), "{dump}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_consumed_len() -> anyhow::Result<()> {
+        let lua = wireshark_lua()?;
+        lua.load(include_str!("utils.lua")).exec()?;
+        lua.load(mlua::chunk! {
+            local experts = { size_mismatch = {} }
+            local buffer = Tvb("abcdefgh")
+            // Fields without a declared length, or that consumed it exactly, are not reported.
+            local tree = Tree()
+            assert(check_consumed_len(tree, experts, buffer, "Size(a)", nil, 1, 3) == 3)
+            assert(check_consumed_len(tree, experts, buffer, "Size(a)", 2, 1, 3) == 3)
+            // Neither are fields cut short by the end of the buffer, which is reported elsewhere.
+            assert(check_consumed_len(tree, experts, buffer, "Size(a)", 6, 4, 8) == 8)
+            assert(tree:dump() == "", tree:dump())
+            // Fields that consumed more than declared are reported.
+            tree = Tree()
+            assert(check_consumed_len(tree, experts, buffer, "Size(a)", 2, 1, 4) == 4)
+            assert(tree:dump() == "expert: Error: Size(a)=2 but 3 bytes consumed", tree:dump())
+            // Fields that consumed less are reported, and the rest is skipped as undecoded.
+            tree = Tree()
+            assert(check_consumed_len(tree, experts, buffer, "Size(a)", 5, 1, 3) == 6)
+            assert(tree:dump() == "expert: Error: Size(a)=5 but 2 bytes consumed\nUndecoded (3 bytes)", tree:dump())
+            // Up to the end of the buffer.
+            tree = Tree()
+            assert(check_consumed_len(tree, experts, buffer, "Size(a)", 10, 1, 3) == 8)
+            assert(tree:dump() == "expert: Error: Size(a)=10 but 2 bytes consumed\nUndecoded (5 bytes)", tree:dump())
+            // Offsets are floats after bit fields, which are shown without a fraction.
+            tree = Tree()
+            assert(check_consumed_len(tree, experts, buffer, "Size(a)", 2, 1.0, 2.0) == 3)
+            assert(tree:dump() == "expert: Error: Size(a)=2 but 1 bytes consumed\nUndecoded (1 bytes)", tree:dump())
+        })
+        .exec()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_dissect_errors_are_caught() -> anyhow::Result<()> {
+        let lua = load_dissector(
+            indoc! {r#"
+                little_endian_packets
+                packet Outer {
+                    _size_(_body_): 8,
+                    _body_,
+                    trailer: 8,
+                }
+                packet Inner : Outer {
+                    a: 4,
+                    b: 12,
+                }
+            "#},
+            Args {
+                target_packets: vec!["Outer".into()],
+                ..Default::default()
+            },
+        )?;
+        // The body is one byte, so reading `b` raises an error in `Inner_dissect`.
+        let dump: String = lua
+            .load(mlua::chunk! {
+                local tree = Tree()
+                local buffer = Tvb(string.char(0x01, 0x12, 0x34))
+                local i, packet_chain = Outer_dissect(buffer, new_pinfo(buffer:len()), tree, Outer_protocol_fields_table, Outer_protocol_experts, "outer")
+                // The trailer after the body is still dissected.
+                assert(i == 3, i)
+                assert(packet_chain == "Inner", packet_chain)
+                return tree:dump()
+            })
+            .eval()?;
+        let lines = dump.lines().collect::<Vec<_>>();
+        let [size, packet, a, overrun, malformed, error, trailer] = lines[..] else {
+            panic!("{dump}");
+        };
+        assert_eq!(size, "Size(_body_)");
+        assert_eq!(packet, "Inner");
+        assert_eq!(a, "  0001 .... = a: 1");
+        assert!(
+            overrun.starts_with("  expert: Expected 1.5 bytes"),
+            "{overrun}"
+        );
+        assert_eq!(malformed, "  [Malformed]");
+        assert!(
+            error.starts_with("    expert: Error: Failed to dissect `Inner.b`: ")
+                && error.ends_with("Range is out of bounds"),
+            "{error}"
+        );
+        assert_eq!(trailer, "trailer");
+        Ok(())
+    }
+
+    #[test]
+    fn test_tcp_framing() -> anyhow::Result<()> {
+        let pdl = indoc! {r#"
+            big_endian_packets
+            struct Header {
+                version: 8,
+            }
+            packet Message {
+                header: Header,
+                _reserved_: 4,
+                _size_(_payload_): 12,
+                _size_(extensions): 16,
+                _payload_,
+                extensions: 8[],
+                checksum: 16,
+            }
+            packet Stream {
+                _size_(_payload_): 8,
+                items: 8[],
+                _payload_,
+            }
+            packet Wide {
+                _size_(_payload_): 64,
+                _payload_,
+            }
+        "#};
+        let args = |target: &str| Args {
+            target_packets: vec![target.into()],
+            framing: Framing::Tcp,
+            ..Default::default()
+        };
+        let lua = load_dissector(pdl, args("Message"))?;
+        lua.load(mlua::chunk! {
+            local function dissect(bytes, can_desegment)
+                local tree = Tree()
+                local pinfo = new_pinfo(#bytes)
+                pinfo.can_desegment = can_desegment
+                local len = Message_protocol.dissector(Tvb(bytes), pinfo, tree)
+                return len, pinfo, #tree.children
+            end
+            // A PDU with a payload of 1 byte and no extensions, whose length is known from its
+            // first 5 bytes.
+            local pdu = string.char(0x01, 0x00, 0x01, 0x00, 0x00, 0xaa, 0x12, 0x34)
+            assert(Message_pdu_len(Tvb(pdu:sub(1, 4)), 0) == nil)
+            assert(Message_pdu_len(Tvb(pdu:sub(1, 5)), 0) == 8)
+            assert(Message_pdu_len(Tvb(pdu .. pdu), 8) == 8)
+            // Each PDU of a segment is dissected.
+            local len, pinfo, pdu_count = dissect(pdu .. pdu, 1)
+            assert(len == 16 and pdu_count == 2 and pinfo.desegment_len == nil)
+            // The PDU at the end of a segment is reassembled with the next segment, whether its
+            // length is known...
+            len, pinfo, pdu_count = dissect(pdu .. pdu:sub(1, 6), 1)
+            assert(len == 14 and pdu_count == 1)
+            assert(pinfo.desegment_offset == 8 and pinfo.desegment_len == 2)
+            // ...or not.
+            len, pinfo, pdu_count = dissect(pdu .. pdu:sub(1, 3), 1)
+            assert(len == 11 and pdu_count == 1)
+            assert(pinfo.desegment_offset == 8 and pinfo.desegment_len == DESEGMENT_ONE_MORE_SEGMENT)
+            // Without reassembly, it is dissected as it is.
+            len, pinfo, pdu_count = dissect(pdu .. pdu:sub(1, 6), 0)
+            assert(len == 14 and pdu_count == 2 and pinfo.desegment_len == nil)
+        })
+        .exec()?;
+
+        // Sizes wider than 32 bits are read as `UInt64`s.
+        let lua = load_dissector(pdl, args("Wide"))?;
+        lua.load(mlua::chunk! {
+            local buffer = Tvb(string.char(0, 0, 0, 0, 0, 0, 0, 2, 0xaa, 0xbb, 0))
+            assert(Wide_pdu_len(buffer, 0) == 10)
+            local pinfo = new_pinfo(buffer:len())
+            pinfo.can_desegment = 1
+            assert(Wide_protocol.dissector(buffer, pinfo, Tree()) == 11)
+            assert(pinfo.desegment_offset == 10 and pinfo.desegment_len == DESEGMENT_ONE_MORE_SEGMENT)
+        })
+        .exec()?;
+
+        // The payload of `Stream` is not at a fixed offset
+        let error = Generator::from_source("framing.pdl", pdl)
+            .target("Stream")
+            .framing(Framing::Tcp)
+            .generate(&mut SourceDatabase::new(), &mut vec![])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("The length of `Stream` cannot be computed from its header"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiple_framing() -> anyhow::Result<()> {
+        // Each PDU is dissected, without reporting the following PDUs as undissected.
+        let lua = load_dissector(
+            include_str!("../tests/test_le.pdl"),
+            Args {
+                target_packets: vec!["TopLevel".into()],
+                framing: Framing::Multiple,
+                ..Default::default()
+            },
+        )?;
+        let simple_packet = [0, 1, 0, 0, 0, 0, 0, 0, 0];
+        let dump = dump(&dissect(&lua, "TopLevel", &simple_packet.repeat(2))?)?;
+        assert_eq!(
+            dump.lines().filter(|line| *line == "TopLevel").count(),
+            2,
+            "{dump}"
+        );
+        assert!(!dump.contains("undissected bits remaining"), "{dump}");
+
+        let lua = load_dissector(
+            indoc! {r#"
+                little_endian_packets
+                packet Message {
+                    kind: 8,
+                    value: 8,
+                }
+            "#},
+            Args {
+                target_packets: vec!["Message".into()],
+                framing: Framing::Multiple,
+                ..Default::default()
+            },
+        )?;
+        let dump: String = lua
+            .load(mlua::chunk! {
+                local buffer = Tvb(string.char(1, 2, 3, 4, 5, 6))
+                local pinfo = new_pinfo(buffer:len())
+                pinfo.cols.info:set("Set by the lower layers")
+                local tree = Tree()
+                assert(Message_protocol.dissector(buffer, pinfo, tree) == 6)
+                assert(tostring(pinfo.cols.info) == "Set by the lower layers (3 messages)", tostring(pinfo.cols.info))
+                return tree:dump()
+            })
+            .eval()?;
+        assert_eq!(
+            dump,
+            indoc! {"
+                Message
+                  kind
+                  value
+                Message
+                  kind
+                  value
+                Message
+                  kind
+                  value"}
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_info_and_ports() -> anyhow::Result<()> {
+        let pdl = indoc! {r#"
+            little_endian_packets
+            enum Opcode : 8 {
+                READ = 1,
+                WRITE = 2,
+            }
+            packet Request {
+                opcode: Opcode,
+                handle: 16,
+            }
+        "#};
+        let args = |framing| Args {
+            target_packets: vec!["Request".into()],
+            tcp_ports: vec![7000],
+            udp_ports: vec![7000, 7001],
+            info: Some("{opcode} {{{handle}}}".into()),
+            framing,
+            ..Default::default()
+        };
+        let info = |lua: &mlua::Lua, bytes| -> anyhow::Result<String> {
+            let tree = dissect(lua, "Request", bytes)?;
+            let columns: mlua::Table = tree.get::<_, mlua::Table>("pinfo")?.get("cols")?;
+            Ok(columns.get::<_, mlua::Table>("info")?.get("text")?)
+        };
+        let lua = load_dissector(pdl, args(Framing::Single))?;
+        lua.load(mlua::chunk! {
+            assert(DissectorTable.added["tcp.port"][7000] == Request_protocol)
+            assert(DissectorTable.added["udp.port"][7000] == Request_protocol)
+            assert(DissectorTable.added["udp.port"][7001] == Request_protocol)
+        })
+        .exec()?;
+        assert_eq!(info(&lua, &[2, 0x34, 0x12])?, "WRITE {4660}");
+
+        // The text of each PDU after the first is appended. Values without a tag are shown as is.
+        let lua = load_dissector(pdl, args(Framing::Multiple))?;
+        assert_eq!(
+            info(&lua, &[1, 1, 0, 3, 2, 0])?,
+            "READ {1}, 3 {2} (2 messages)"
+        );
+
+        let error = Generator::from_source("request.pdl", pdl)
+            .target("Request")
+            .info("{Opcode}")
+            .generate(&mut SourceDatabase::new(), &mut vec![])
+            .unwrap_err();
+        assert!(error.to_string().contains(
+            "The Info template refers to `Opcode`, which is not a scalar or enum field of `Request`"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_dissector_handoff() -> anyhow::Result<()> {
+        let lua = load_dissector(
+            indoc! {r#"
+                big_endian_packets
+                enum EtherType : 16 {
+                    IPV4 = 0x0800,
+                    ARP = 0x0806,
+                }
+                packet Tunnel {
+                    ether_type: EtherType,
+                    _size_(frame): 8,
+                    frame: 8[], // Encapsulated frame @dissector_table=ethertype @key=ether_type
+                    _payload_, // @dissector=json
+                }
+            "#},
+            Args {
+                target_packets: vec!["Tunnel".into()],
+                options: DissectorOptions {
+                    expand_byte_arrays: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )?;
+        let tree = dissect(&lua, "Tunnel", b"\x08\x00\x02\xaa\xbb{}")?;
+        lua.load(mlua::chunk! {
+            assert(DissectorTable.tried["ethertype"].key == 0x0800)
+            assert(DissectorTable.tried["ethertype"].bytes == string.char(0xaa, 0xbb))
+            assert(Dissector.called["json"] == "{}")
+        })
+        .exec()?;
+        // The frame is handed off as a whole, even though byte arrays are expanded.
+        let frame = registered_field(&lua, "Tunnel", "tunnel.frame")?;
+        assert_eq!(frame.get::<_, String>("description")?, "Encapsulated frame");
+        assert_eq!(
+            field_value::<mlua::String>(&tree, "tunnel.frame")?,
+            [0xaa, 0xbb][..]
+        );
+        assert_eq!(
+            dump(&tree)?,
+            indoc! {"
+                Tunnel
+                  ether_type
+                  Size(frame)
+                  frame
+                  Payload"}
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bound_pdl_files() -> anyhow::Result<()> {
+        let mut app_file = tempfile::NamedTempFile::new()?;
+        write!(
+            app_file,
+            "{}",
+            indoc! {r#"
+                big_endian_packets
+                struct Header {
+                    opcode: 16,
+                }
+                packet Data {
+                    header: Header,
+                    value: 32,
+                }
+            "#}
+        )?;
+        let binding = format!("Frame._payload_={}:Data", app_file.path().display());
+        let lua = load_dissector(
+            indoc! {r#"
+                little_endian_packets
+                struct Header {
+                    seq: 16,
+                }
+                packet Frame {
+                    header: Header,
+                    _size_(_payload_): 8,
+                    _payload_,
+                }
+            "#},
+            Args {
+                target_packets: vec!["Frame".into()],
+                options: DissectorOptions {
+                    bindings: vec![binding.parse().map_err(anyhow::Error::msg)?],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )?;
+        // The bound protocol is named after its field, as `data` is taken by a built-in protocol.
+        let protocol: mlua::Table = lua
+            .globals()
+            .get::<_, mlua::Table>("Proto")?
+            .get::<_, mlua::Table>("registered")?
+            .get("frame.payload")?;
+        assert_eq!(protocol.get::<_, String>("name")?, "Frame.payload");
+        assert_eq!(
+            protocol.get::<_, String>("description")?,
+            "Data (Frame.payload)"
+        );
+        // The bound file is namespaced.
+        assert!(lua.globals().contains_key("Frame_protocol")?);
+        assert!(!lua.globals().contains_key("Data_protocol")?);
+
+        // The payload is handed to the bound protocol. Each file keeps its own endianness, and
+        // its own `Header`.
+        let tree = dissect(&lua, "Frame", &[1, 0, 6, 0, 2, 0, 0, 0, 3])?;
+        assert_eq!(field_value::<u32>(&tree, "frame.header.seq")?, 1);
+        assert_eq!(field_value::<u32>(&tree, "frame.payload.header.opcode")?, 2);
+        assert_eq!(field_value::<u32>(&tree, "frame.payload.value")?, 3);
+        assert_eq!(
+            dump(&tree)?,
+            indoc! {"
+                Frame
+                  header
+                    seq
+                  Size(Payload)
+                  Payload
+                  Data (Frame.payload)
+                    header
+                      opcode
+                    value"}
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_module() -> anyhow::Result<()> {
+        let mut link_file = tempfile::NamedTempFile::new()?;
+        write!(
+            link_file,
+            "little_endian_packets\npacket Frame {{\n  _size_(_payload_): 8,\n  _payload_,\n}}\n"
+        )?;
+        let mut app_file = tempfile::NamedTempFile::new()?;
+        write!(
+            app_file,
+            "{}",
+            indoc! {r#"
+                big_endian_packets
+                enum Opcode : 8 {
+                    GET = 1,
+                    SET = 2,
+                }
+                struct Header {
+                    opcode: Opcode,
+                }
+                packet Message {
+                    header: Header,
+                    value: 32,
+                }
+            "#}
+        )?;
+        let args = || Args {
+            pdl_file: Some(app_file.path().to_path_buf()),
+            target_packets: vec!["Message".into()],
+            module: true,
+            ..Default::default()
+        };
+        let output = String::from_utf8(run_with_args(args()))?;
+        let lua = wireshark_lua()?;
+        let module: mlua::Table = lua.load(&output).eval()?;
+        // Nothing is defined as a global, and nothing is registered.
+        assert!(!lua.globals().contains_key("Message_protocol")?);
+        assert!(!lua.globals().contains_key("ProtoEnum")?);
+        let protocols: mlua::Table = module.get("protocols")?;
+        assert!(protocols.contains_key("Message")?);
+        let dissect: mlua::Table = module.get("dissect")?;
+        assert!(dissect.get::<_, mlua::Function>("Header").is_ok());
+        assert!(dissect.get::<_, mlua::Function>("Message").is_ok());
+        let enums: mlua::Table = module.get("enums")?;
+        assert!(enums.contains_key("Opcode")?);
+        let fields: mlua::Table = module.get("fields")?;
+        let message_fields: mlua::Table = fields.get("Message")?;
+        assert!(message_fields.contains_key("message.value")?);
+        let experts: mlua::Table = module.get("experts")?;
+        assert!(experts.contains_key("Message")?);
+
+        // Bound files are namespaced inside the module, where they see its utilities.
+        let binding = format!("Frame._payload_={}:Message", app_file.path().display());
+        let output = String::from_utf8(run_with_args(Args {
+            pdl_file: Some(link_file.path().to_path_buf()),
+            target_packets: vec!["Frame".into()],
+            options: DissectorOptions {
+                bindings: vec![binding.parse().map_err(anyhow::Error::msg)?],
+                ..Default::default()
+            },
+            ..args()
+        }))?;
+        let lua = wireshark_lua()?;
+        let module: mlua::Table = lua.load(&output).eval()?;
+        let protocols: mlua::Table = module.get("protocols")?;
+        assert!(protocols.contains_key("Frame")?);
+        assert!(!protocols.contains_key("Message")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_bindings() -> anyhow::Result<()> {
+        assert!("Frame._payload_=app.pdl".parse::<Binding>().is_err());
+        assert_eq!(
+            r"Frame.data=C:\app.pdl:Message".parse::<Binding>(),
+            Ok(Binding {
+                decl_id: "Frame".into(),
+                field_id: "data".into(),
+                pdl_file: r"C:\app.pdl".into(),
+                packet: "Message".into(),
+            })
+        );
+        for (binding, message) in [
+            ("Frame.data=app.pdl:Message", "Unable to find field"),
+            (
+                "Frame._payload_=app.pdl:Message",
+                "Fields bound with `--bind` cannot also use `@dissector` or `@dissector_table`",
+            ),
+        ] {
+            let error = Generator::from_source(
+                "frame.pdl",
+                "little_endian_packets\npacket Frame {\n  kind: 8,\n  _payload_, // @dissector=ip\n}\n",
+            )
+            .target("Frame")
+            .bind(binding.parse().map_err(anyhow::Error::msg)?)
+            .generate(&mut SourceDatabase::new(), &mut vec![])
+            .unwrap_err()
+            .to_string();
+            assert!(error.contains(message), "{binding}: {error}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_generator() -> anyhow::Result<()> {
+        let source = indoc! {r#"
+            big_endian_packets
+            packet Status {
+                code: 8,
+                flags: 8,
+            }
+        "#};
+        let mut sources = SourceDatabase::new();
+        let mut output = vec![];
+        Generator::from_source("status.pdl", source)
+            .target("Status")
+            .protocol_name("STATUS")
+            .abbr_style(AbbrStyle::Lowercase)
+            .boolean_field("Status", "flags")
+            .framing(Framing::Multiple)
+            .generate(&mut sources, &mut output)?;
+        let lua = wireshark_lua()?;
+        lua.load(output).exec()?;
+        let protocol: mlua::Table = lua.globals().get("Status_protocol")?;
+        assert_eq!(protocol.get::<_, String>("name")?, "STATUS");
+        let flags = registered_field(&lua, "Status", "status.flags")?;
+        assert_eq!(flags.get::<_, String>("type_name")?, "BOOLEAN");
+
+        // An already analyzed file can be generated from, e.g. one that is also compiled to Rust.
+        let file = pdl_compiler::parser::parse_inline(&mut sources, "again.pdl", source.into())
+            .map_err(Diagnostics::from)?;
+        let analyzed_file = pdl_compiler::analyzer::analyze(&file).map_err(Diagnostics::from)?;
+        let mut output = vec![];
+        Generator::from_analyzed(analyzed_file)
+            .target("Status")
+            .generate(&mut sources, &mut output)?;
+        let lua = wireshark_lua()?;
+        lua.load(output).exec()?;
+        let protocol: mlua::Table = lua.globals().get("Status_protocol")?;
+        assert_eq!(protocol.get::<_, String>("name")?, "Status");
+
+        // Errors are returned as diagnostics, and nothing is written.
+        let mut output = vec![];
+        let error = Generator::from_source("status.pdl", source)
+            .target("Status")
+            .boolean_field("Status", "missing")
+            .generate(&mut sources, &mut output)
+            .unwrap_err();
+        assert_eq!(
+            error.as_slice()[0].message,
+            r#"Unable to find field "missing" in "Status""#
+        );
+        assert!(output.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_check() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pdl_file = dir.path().join("packets.pdl");
+        std::fs::write(
+            &pdl_file,
+            "little_endian_packets\npacket Message {\n  value: 16,\n}\n",
+        )?;
+        let args = || Args {
+            pdl_file: Some(pdl_file.clone()),
+            target_packets: vec!["Message".into()],
+            ..Default::default()
+        };
+        let existing = dir.path().join("packets.lua");
+        let generated = String::from_utf8(run_with_args(args()))?;
+        assert!(generated.starts_with(&format!(
+            "-- Generated by pdl-dissector {}. Do not edit, regenerate it instead.\n-- Inputs: sha256:",
+            env!("CARGO_PKG_VERSION")
+        )));
+        std::fs::write(&existing, &generated)?;
+        let mut diff = vec![];
+        let (up_to_date, _) = run_check(args(), &existing, &mut SourceDatabase::new(), &mut diff)?;
+        assert!(up_to_date);
+        assert!(diff.is_empty());
+
+        // Changing the options changes the hash of the inputs, and the dissector.
+        let mut diff = vec![];
+        let (up_to_date, _) = run_check(
+            Args {
+                protocol_name: Some("MSG".into()),
+                ..args()
+            },
+            &existing,
+            &mut SourceDatabase::new(),
+            &mut diff,
+        )?;
+        assert!(!up_to_date);
+        let diff = String::from_utf8(diff)?;
+        assert!(diff.starts_with(&format!("--- {}\n+++ generated\n", existing.display())));
+        assert!(diff.contains("\n--- Inputs: sha256:"));
+        assert!(diff.contains("\n-Message_protocol = Proto(\"Message\",  \"Message\")\n"));
+        assert!(diff.contains("\n+Message_protocol = Proto(\"MSG\",  \"MSG\")\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_dissector_handoff() -> anyhow::Result<()> {
+        for (fields, message) in [
+            (
+                "_payload_, // @dissector=ip @dissector_table=udp.port @key=kind",
+                "Only one of `@dissector` and `@dissector_table` can be used",
+            ),
+            (
+                "_payload_, // @dissector_table=udp.port",
+                "`@dissector_table` requires the field to look up",
+            ),
+            (
+                "_payload_, // @dissector_table=udp.port @key=port",
+                "Unable to find the key field `port` before this field in `Test`",
+            ),
+            (
+                "data: 8[4], // @dissector_table=udp.port @key=data",
+                "Unable to find the key field `data` before this field in `Test`",
+            ),
+            (
+                "value: 16, // @dissector=ip",
+                "Only payloads and byte arrays can be handed to another dissector",
+            ),
+        ] {
+            let source =
+                format!("little_endian_packets\npacket Test {{\n  kind: 8,\n  {fields}\n}}\n");
+            let error = Generator::from_source("test.pdl", source)
+                .target("Test")
+                .generate(&mut SourceDatabase::new(), &mut vec![])
+                .unwrap_err()
+                .to_string();
+            assert!(error.contains(message), "{fields}: {error}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_subcommands() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pdl_file = dir.path().join("packets.pdl");
+        std::fs::write(
+            &pdl_file,
+            indoc! {r#"
+                little_endian_packets
+                enum Kind : 8 {
+                    A = 1,
+                    B = 2,
+                }
+                packet Parent {
+                    kind: Kind,
+                    _payload_,
+                }
+                packet ChildA : Parent (kind = A) {
+                    value: 8,
+                }
+                packet Message {
+                    value: 16,
+                }
+            "#},
+        )?;
+        let run_command_line = |command_line: &[&str]| -> anyhow::Result<(bool, String)> {
+            let matches = Cli::command().try_get_matches_from(
+                ["pdl-dissector"]
+                    .iter()
+                    .chain(command_line)
+                    .map(|arg| arg.replace("PDL_FILE", pdl_file.to_str().unwrap())),
+            )?;
+            let cli = Cli::from_arg_matches(&matches)?;
+            let mut output = vec![];
+            let (succeeded, _) = run_cli(cli, &matches, &mut SourceDatabase::new(), &mut output)?;
+            Ok((succeeded, String::from_utf8(output)?))
+        };
+
+        let (succeeded, listed) = run_command_line(&["list", "PDL_FILE"])?;
+        assert!(succeeded);
+        assert_eq!(
+            listed,
+            indoc! {"
+                enum Kind : 8 (2 tags)
+                packet Parent
+                    packet ChildA (kind = A)
+                packet Message
+            "}
+        );
+
+        // Without a subcommand, the dissector is generated like with `generate`.
+        let (_, generated) = run_command_line(&["generate", "PDL_FILE", "Message"])?;
+        assert!(generated.contains("Message_protocol = Proto("));
+        assert_eq!(run_command_line(&["PDL_FILE", "Message"])?.1, generated);
+
+        assert_eq!(
+            run_command_line(&["check", "PDL_FILE"])?,
+            (true, String::new())
+        );
+        // Errors in the PDL file are the result of `check`, while a missing file is an error.
+        assert_eq!(
+            run_command_line(&["check", "PDL_FILE", "Missing"])?,
+            (false, String::new())
+        );
+        let error = run_command_line(&["check", "missing.pdl"]).unwrap_err();
+        assert!(error.to_string().contains("Unable to find missing.pdl"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_unsupported() -> anyhow::Result<()> {
+        let mut pdl_file = tempfile::NamedTempFile::new()?;
+        write!(
+            pdl_file,
+            "little_endian_packets\nstruct Item {{\n  a: 8,\n}}\npacket Test {{\n  _elementsize_(items): 8,\n  items: Item[],\n}}\n"
+        )?;
+        let args = Args {
+            pdl_file: Some(pdl_file.path().to_path_buf()),
+            ..Default::default()
+        };
+        let error = run_analysis(args, &mut SourceDatabase::new()).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`_elementsize_` fields are not supported"));
+        Ok(())
+    }
+
+    /// Generates the dissector of the inline PDL source `pdl` with `args`, whose PDL file is
+    /// replaced, and loads it into the fake Wireshark Lua API.
+    fn load_dissector(pdl: &str, args: Args) -> anyhow::Result<mlua::Lua> {
+        let mut pdl_file = tempfile::NamedTempFile::new()?;
+        write!(pdl_file, "{pdl}")?;
+        let output = run_with_args(Args {
+            pdl_file: Some(pdl_file.path().to_path_buf()),
+            ..args
+        });
+        let lua = wireshark_lua()?;
+        lua.load(output).exec()?;
+        Ok(lua)
+    }
+
+    /// Dissects `bytes` as a frame of the protocol of `packet`, and returns the tree it added
+    /// its items to, with the packet info of the frame as its `pinfo`.
+    fn dissect<'lua>(
+        lua: &'lua mlua::Lua,
+        packet: &str,
+        bytes: &[u8],
+    ) -> anyhow::Result<mlua::Table<'lua>> {
+        let protocol: mlua::Table = lua.globals().get(format!("{packet}_protocol"))?;
+        let dissect: mlua::Function = lua
+            .load(mlua::chunk! {
+                return function(protocol, bytes)
+                    local buffer = Tvb(bytes)
+                    local tree = Tree()
+                    tree.pinfo = new_pinfo(buffer:len())
+                    protocol.dissector(buffer, tree.pinfo, tree)
+                    return tree
+                end
+            })
+            .eval()?;
+        Ok(dissect.call((protocol, lua.create_string(bytes)?))?)
+    }
+
+    /// The field registered with the filter name `abbr` by the protocol of `packet`.
+    fn registered_field<'lua>(
+        lua: &'lua mlua::Lua,
+        packet: &str,
+        abbr: &str,
+    ) -> anyhow::Result<mlua::Table<'lua>> {
+        let fields: mlua::Table = lua
+            .globals()
+            .get(format!("{packet}_protocol_fields_table"))?;
+        let field: mlua::Table = fields.get(abbr)?;
+        Ok(field.get("field")?)
+    }
+
+    /// The labels of the items of `tree`, one per line and indented by depth.
+    fn dump(tree: &mlua::Table) -> anyhow::Result<String> {
+        Ok(tree.call_method("dump", ())?)
+    }
+
+    /// The value of the first item of the field with the filter name `abbr` in `tree`.
+    fn field_value<'lua, T: mlua::FromLua<'lua>>(
+        tree: &mlua::Table<'lua>,
+        abbr: &str,
+    ) -> anyhow::Result<T> {
+        let item: mlua::Table = tree
+            .call_method::<_, Option<mlua::Table>>("find", abbr)?
+            .ok_or_else(|| anyhow::anyhow!("No item of `{abbr}` in:\n{}", dump(tree).unwrap()))?;
+        Ok(item.call_method("field_value", ())?)
+    }
+
+    pub(crate) fn run_with_args(args: Args) -> Vec<u8> {
         let mut writer = BufWriter::new(Vec::new());
         run(args, &mut SourceDatabase::new(), &mut writer).unwrap();
         writer.into_inner().unwrap()
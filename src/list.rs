@@ -0,0 +1,65 @@
+//! Listing the declarations of a PDL file, with child packets and structs under their parents.
+
+use std::io::Write;
+
+use pdl_compiler::{
+    analyzer::{self, Scope},
+    ast::{Decl, DeclDesc},
+};
+
+use crate::diagnostics::Diagnostics;
+
+/// Writes the declarations of `file` in the order they are declared, one per line, e.g.
+/// `packet TopLevel`, with children indented under their parents and followed by their
+/// constraints, e.g. `packet SimplePacket (type = Simple)`.
+pub fn write_declarations(
+    file: &analyzer::ast::File,
+    writer: &mut impl Write,
+) -> Result<(), Diagnostics> {
+    let scope = Scope::new(file)?;
+    for decl in &file.declarations {
+        if decl.parent_id().is_none() && !matches!(decl.desc, DeclDesc::Test { .. }) {
+            write_declaration(decl, &scope, 0, writer)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_declaration(
+    decl: &Decl<analyzer::ast::Annotation>,
+    scope: &Scope,
+    depth: usize,
+    writer: &mut impl Write,
+) -> std::io::Result<()> {
+    let indent = "    ".repeat(depth);
+    let id = decl.id().unwrap_or_default();
+    let description = match &decl.desc {
+        DeclDesc::Packet { .. } => format!("packet {id}"),
+        DeclDesc::Struct { .. } => format!("struct {id}"),
+        DeclDesc::Group { .. } => format!("group {id}"),
+        DeclDesc::Enum { tags, width, .. } => format!("enum {id} : {width} ({} tags)", tags.len()),
+        DeclDesc::Checksum { width, .. } => format!("checksum {id} : {width}"),
+        DeclDesc::CustomField {
+            width: Some(width), ..
+        } => format!("custom_field {id} : {width}"),
+        DeclDesc::CustomField { width: None, .. } => format!("custom_field {id}"),
+        DeclDesc::Test { .. } => return Ok(()),
+    };
+    let constraints = decl
+        .constraints()
+        .map(|constraint| match (&constraint.value, &constraint.tag_id) {
+            (Some(value), _) => format!("{} = {value}", constraint.id),
+            (None, Some(tag_id)) => format!("{} = {tag_id}", constraint.id),
+            (None, None) => constraint.id.clone(),
+        })
+        .collect::<Vec<_>>();
+    if constraints.is_empty() {
+        writeln!(writer, "{indent}{description}")?;
+    } else {
+        writeln!(writer, "{indent}{description} ({})", constraints.join(", "))?;
+    }
+    for child in scope.iter_children(decl) {
+        write_declaration(child, scope, depth + 1, writer)?;
+    }
+    Ok(())
+}
@@ -1,15 +1,22 @@
-use clap::Parser as _;
+use std::process::ExitCode;
+
+use clap::{CommandFactory as _, FromArgMatches as _};
 use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
 use pdl_compiler::ast::SourceDatabase;
 
-fn main() {
-    let args = pdl_dissector::Args::parse();
+fn main() -> ExitCode {
+    let _ = env_logger::try_init();
+    let matches = pdl_dissector::Cli::command().get_matches();
+    let cli = pdl_dissector::Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
     let mut sources = SourceDatabase::new();
-    match pdl_dissector::run(args, &mut sources, &mut std::io::stdout()) {
-        Ok(_) => {}
-        Err(diag) => {
-            let mut writer = StandardStream::stderr(ColorChoice::Always);
-            diag.emit(&sources, &mut writer).unwrap();
-        }
-    }
+    let mut writer = StandardStream::stderr(ColorChoice::Always);
+    // Like `diff`, 1 is a negative result, e.g. an outdated dissector, and 2 is an error.
+    let (exit_code, diagnostics) =
+        match pdl_dissector::run_cli(cli, &matches, &mut sources, &mut std::io::stdout()) {
+            Ok((true, warnings)) => (ExitCode::SUCCESS, warnings),
+            Ok((false, diagnostics)) => (ExitCode::from(1), diagnostics),
+            Err(errors) => (ExitCode::from(2), errors),
+        };
+    diagnostics.emit(&sources, &mut writer).unwrap();
+    exit_code
 }
@@ -0,0 +1,99 @@
+/// How the names of declarations and fields are written in the filter names (abbreviations) of the
+/// generated fields.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AbbrStyle {
+    /// Use the names as they are written in the PDL file, e.g. `toplevel.SimplePacket.value`.
+    #[default]
+    AsIs,
+    /// Convert the names to snake case, e.g. `toplevel.simple_packet.value`.
+    SnakeCase,
+    /// Convert the names to lowercase, e.g. `toplevel.simplepacket.value`.
+    Lowercase,
+}
+
+impl AbbrStyle {
+    /// Applies this style to one segment of a filter name.
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            AbbrStyle::AsIs => name.to_string(),
+            AbbrStyle::SnakeCase => to_snake_case(name),
+            AbbrStyle::Lowercase => name.to_lowercase(),
+        }
+    }
+}
+
+fn to_snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut output = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let prev = i.checked_sub(1).map(|i| chars[i]);
+            let next = chars.get(i + 1);
+            let is_word_start = match prev {
+                None | Some('_') => false,
+                Some(p) if p.is_lowercase() || p.is_ascii_digit() => true,
+                Some(p) => p.is_uppercase() && next.is_some_and(|n| n.is_lowercase()),
+            };
+            if is_word_start {
+                output.push('_');
+            }
+            output.extend(c.to_lowercase());
+        } else {
+            output.push(*c);
+        }
+    }
+    output
+}
+
+/// Names of a generated protocol.
+#[derive(Clone, Debug)]
+pub struct ProtocolNames {
+    /// The short name, which is shown in the "Protocol" column. Wireshark derives the filter name
+    /// of the protocol by lowercasing this name.
+    pub short_name: String,
+    /// The descriptive name, which is shown in the protocol tree.
+    pub full_name: String,
+    /// The prefix of the filter names of all fields in this protocol.
+    pub filter_prefix: String,
+}
+
+impl ProtocolNames {
+    /// The descriptive name, escaped to be written in a Lua string literal.
+    pub fn escaped_full_name(&self) -> String {
+        self.full_name.replace('\\', "\\\\").replace('"', "\\\"")
+    }
+}
+
+/// Returns whether `name` can be used as (part of) a display filter field name.
+pub fn is_valid_filter_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AbbrStyle;
+
+    #[test]
+    fn test_snake_case() {
+        let style = AbbrStyle::SnakeCase;
+        assert_eq!("top_level", style.apply("TopLevel"));
+        assert_eq!(
+            "group_ask_brew_history",
+            style.apply("Group_AskBrewHistory")
+        );
+        assert_eq!("unaligned_enum_packet", style.apply("UnalignedEnum_packet"));
+        assert_eq!("hci_packet", style.apply("HCIPacket"));
+        assert_eq!("_payload__size", style.apply("_payload__size"));
+        assert_eq!("scalar_value", style.apply("scalar_value"));
+    }
+
+    #[test]
+    fn test_lowercase() {
+        assert_eq!("toplevel", AbbrStyle::Lowercase.apply("TopLevel"));
+        assert_eq!("TopLevel", AbbrStyle::AsIs.apply("TopLevel"));
+    }
+}
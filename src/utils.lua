@@ -1,16 +1,102 @@
 -- Utils section
-function enforce_len_limit(num, limit, tree)
+
+-- Raised when a field extends past the captured bytes, but not past the reported length of the
+-- frame, e.g. because the capture was taken with a small snaplen. Like the exceptions of the C
+-- dissectors, it stops the dissection of the whole packet, which is then shown as truncated rather
+-- than malformed.
+PACKET_TRUNCATED = setmetatable({}, {
+    __tostring = function() return "Packet size limited during capture" end,
+})
+
+-- Raises `PACKET_TRUNCATED` if a field starting at `offset` in `buffer` needs `needed_len` bytes
+-- past its captured bytes, but not past its reported length, e.g. because the capture was taken
+-- with a small snaplen. `needed_len` can be nil if it is unknown. Sub-buffers of fields with a
+-- known length are fully captured, so errors inside them are reported as malformed.
+function check_truncated(buffer, offset, needed_len)
+    if buffer:len() >= buffer:reported_len() then
+        return
+    end
+    if needed_len == nil or offset + needed_len <= buffer:reported_len() then
+        error(PACKET_TRUNCATED, 0)
+    end
+end
+
+-- Marks the packet in `tree` as truncated if `err` is `PACKET_TRUNCATED`, and raises `err` again
+-- otherwise. Returns the offset after the captured bytes.
+function report_truncated(tree, buffer, protocol_name, err)
+    if err ~= PACKET_TRUNCATED then
+        error(err, 0)
+    end
+    tree:add("[Packet size limited during capture: " .. protocol_name .. " truncated]")
+    tree:append_text(" [" .. buffer:len() .. " of " .. buffer:reported_len() .. " bytes captured]")
+    return buffer:len()
+end
+
+-- Returns `num`, the length of a field at the start of `buffer`, limited to the captured bytes.
+function enforce_len_limit(num, buffer, tree, experts)
+    local limit = buffer:len()
     if num == nil then
         return limit
     end
     if num > limit then
-        tree:add_expert_info(PI_MALFORMED, PI_ERROR,
+        check_truncated(buffer, 0, num)
+        tree:add_proto_expert_info(experts.length_overrun,
             "Expected " .. num .. " bytes, but only " .. limit .. " bytes remaining")
         return limit
     end
     return num
 end
 
+-- Returns the bytes of a field of `field_len` bytes at `i` to dissect it from. Fields with a
+-- declared length are limited to it, while the others keep the reported length of `buffer`, so
+-- that fields inside them that were cut off by the capture are reported as truncated.
+function field_buffer(buffer, i, field_len, declared_len)
+    if declared_len == nil then
+        return buffer(i)
+    end
+    return buffer(i, field_len)
+end
+
+-- Checks that a field starting at `start` with a declared length of `declared_len` bytes ended at
+-- `i`, and returns the offset after the field. Bytes that were declared but not dissected are added
+-- to `tree` as an undecoded item and skipped. Missing bytes are already reported by
+-- `enforce_len_limit` and the array loops, so they are not reported again.
+function check_consumed_len(tree, experts, buffer, size_name, declared_len, start, i)
+    local consumed_len = i - start
+    if declared_len == nil or consumed_len == declared_len then
+        return i
+    end
+    if consumed_len < declared_len and i >= buffer:len() then
+        return i
+    end
+    tree:add_proto_expert_info(experts.size_mismatch,
+        "Error: " .. size_name .. "=" .. declared_len .. " but " .. string.format("%g", consumed_len) .. " bytes consumed")
+    if consumed_len > declared_len then
+        return i
+    end
+    local undecoded_len = math.min(declared_len - consumed_len, buffer:len() - i)
+    tree:add(buffer(i, undecoded_len), "Undecoded (" .. string.format("%g", undecoded_len) .. " bytes)")
+    return i + undecoded_len
+end
+
+-- Reports an error raised while dissecting `field_name` of the declaration `decl_name`, and marks
+-- the rest of `buffer` from `i` as malformed. Returns the offset after the marked bytes.
+-- `PACKET_TRUNCATED` is raised again, so that it stops the dissection of the whole packet.
+function report_dissect_error(tree, experts, buffer, i, decl_name, field_name, err)
+    if err == PACKET_TRUNCATED then
+        error(err, 0)
+    end
+    local start = math.min(math.floor(i), buffer:len())
+    local malformed = tree:add(buffer(start, buffer:len() - start), "[Malformed]")
+    local location = decl_name
+    if field_name ~= nil then
+        location = decl_name .. "." .. field_name
+    end
+    malformed:add_proto_expert_info(experts.dissect_error,
+        "Error: Failed to dissect `" .. location .. "`: " .. tostring(err))
+    return buffer:len()
+end
+
 function sum_or_nil(...)
     local sum = 0
     local params = table.pack(...)
@@ -65,10 +151,12 @@ function AlignedProtoField:new(o)
         ftype = nil,
         valuestring = nil,
         base = nil,
+        unit = nil, -- optional
         is_little_endian = nil,
         description = nil, -- optional
     }
-    o.field = ProtoField.new(o.name, o.abbr, o.ftype, o.valuestring, o.base, nil, o.description)
+    local valuestring, field_base = get_valuestring_and_base(o, o.valuestring)
+    o.field = ProtoField.new(o.name, o.abbr, o.ftype, valuestring, field_base, nil, o.description)
     setmetatable(o, self)
     self.__index = self
     return o
@@ -93,9 +181,20 @@ function UnalignedProtoField:new(o)
         bitoffset = nil,
         bitlen = nil, -- optional
         valuestring = nil, -- optional
+        base = nil, -- optional
+        unit = nil, -- optional
         description = nil, -- optional
     }
-    o.field = ProtoField.new(o.name, o.abbr, o.ftype, nil, nil, nil, o.description)
+    if o.ftype == ftypes.BOOLEAN then
+        -- Booleans are registered with a bitmask over the bytes they span
+        local numbytes = math.ceil((o.bitoffset + o.bitlen) / 8)
+        o.value_shift = 2 ^ (numbytes * 8 - o.bitoffset - o.bitlen)
+        local mask = (2 ^ o.bitlen - 1) * o.value_shift
+        o.field = ProtoField.new(o.name, o.abbr, o.ftype, o.valuestring, numbytes * 8, mask, o.description)
+    else
+        local valuestring, field_base = get_valuestring_and_base(o, nil)
+        o.field = ProtoField.new(o.name, o.abbr, o.ftype, valuestring, field_base, nil, o.description)
+    end
     setmetatable(o, self)
     self.__index = self
     return o
@@ -114,11 +213,23 @@ function UnalignedProtoField:dissect(tree, buffer, runtime_len)
     label = label .. string.rep(".", numbytes * 8 - bitlen - self.bitoffset)
     label = format_bitstring(label) .. " = " .. self.name
     label = label .. ": " .. self:get_value_display_string(value) -- Print out the string label
-    local subtree = tree:add(self.field, buf, value, label)
+    local item_value = value
+    if self.value_shift ~= nil then
+        -- Wireshark applies the bitmask to the given value, so shift the value back into place
+        item_value = value * self.value_shift
+    end
+    local subtree = tree:add(self.field, buf, item_value, label)
     return subtree, value, bitlen
 end
 
 function UnalignedProtoField:get_value_display_string(value)
+    if self.ftype == ftypes.BOOLEAN and self.valuestring ~= nil then
+        if value ~= 0 then
+            return self.valuestring[1]
+        else
+            return self.valuestring[2]
+        end
+    end
     if self.valuestring ~= nil then
         for _, range in ipairs(self.valuestring) do
             if range[1] <= value and value <= range[2] then
@@ -126,7 +237,30 @@ function UnalignedProtoField:get_value_display_string(value)
             end
         end
     end
-    return value
+    return format_integer(value, self.base) .. nil_coalesce(self.unit, "")
+end
+
+-- Returns the valuestring and base to register a ProtoField with, which displays `o.unit` after
+-- the values if it is set
+function get_valuestring_and_base(o, valuestring)
+    if o.unit ~= nil then
+        return {o.unit}, nil_coalesce(o.base, base.DEC) + base.UNIT_STRING
+    end
+    return valuestring, o.base
+end
+
+-- Formats an integer in the given base, like Wireshark does
+function format_integer(value, display_base)
+    if display_base == base.HEX then
+        return string.format("0x%x", value)
+    elseif display_base == base.OCT then
+        return string.format("0%o", value)
+    elseif display_base == base.DEC_HEX then
+        return string.format("%d (0x%x)", value, value)
+    elseif display_base == base.HEX_DEC then
+        return string.format("0x%x (%d)", value, value)
+    end
+    return tostring(value)
 end
 
 ProtoEnum = {}
@@ -167,6 +301,22 @@ function ProtoEnum:match(enum_name, value)
     return false
 end
 
+-- Formats the value of a field for the Info column: the name of its tag if `enum` is given, or `?`
+-- if the field was not dissected.
+function info_value(value, enum)
+    if value == nil then
+        return "?"
+    end
+    if enum ~= nil then
+        for _, matcher in ipairs(enum.matchers) do
+            if matcher[1] <= value and value <= matcher[2] then
+                return matcher[3]
+            end
+        end
+    end
+    return tostring(value)
+end
+
 -- Add a space every 4 characters in the string
 -- Example: 0010010101 -> 0010 0101 01
 function format_bitstring(input)
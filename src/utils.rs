@@ -21,6 +21,16 @@ pub fn buffer_value_lua_function(endian: EndiannessValue, len: &RuntimeLenInfo)
     }
 }
 
+/// Returns `s` as a quoted Lua string literal.
+pub fn lua_string(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
 pub fn lua_if_then_else<W: std::io::Write + Sized>(
     mut writer: W,
     iter: impl IntoIterator<
@@ -12,8 +12,9 @@ use pdl_dissector::{
 fn golden_test() -> anyhow::Result<()> {
     let mut file = tempfile::NamedTempFile::new()?;
     let args = Args {
-        pdl_file: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/test_le.pdl"),
+        pdl_file: Some(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/test_le.pdl")),
         target_packets: vec![String::from("TopLevel")],
+        ..Default::default()
     };
     let mut sources = SourceDatabase::new();
     pdl_dissector::run(args, &mut sources, &mut file)?;
@@ -69,269 +70,313 @@ fn golden_test() -> anyhow::Result<()> {
     pretty_assertions::assert_eq!(
         vec![
             vec![
-                "[TopLevel.type] type: Simple (0)",
-                "[_ws.lua.text] SimplePacket",
+                "[toplevel.type] type: Simple (0)",
+                "[toplevel._packet_] Packet: SimplePacket",
                 &format!(
-                    "  [TopLevel.SimplePacket.scalar_value] scalar_value: {}",
+                    "  [toplevel.SimplePacket.scalar_value] scalar_value: {}",
                     u64::from_le_bytes(hex!("1234567812345678"))
                 ),
+                "[toplevel._packet_chain_] Packet chain: SimplePacket",
             ],
             vec![
-                "[TopLevel.type] type: Enum (1)",
-                "[_ws.lua.text] EnumPacket",
-                "  [TopLevel.EnumPacket.addition] addition: Empty (0)",
+                "[toplevel.type] type: Enum (1)",
+                "[toplevel._packet_] Packet: EnumPacket",
+                "  [toplevel.EnumPacket.addition] addition: Empty (0)",
+                "[toplevel._packet_chain_] Packet chain: EnumPacket",
             ],
             vec![
-                "[TopLevel.type] type: Enum (1)",
-                "[_ws.lua.text] EnumPacket",
-                "  [TopLevel.EnumPacket.addition] addition: NonAlcoholic: Vanilla (2)",
+                "[toplevel.type] type: Enum (1)",
+                "[toplevel._packet_] Packet: EnumPacket",
+                "  [toplevel.EnumPacket.addition] addition: NonAlcoholic: Vanilla (2)",
+                "[toplevel._packet_chain_] Packet chain: EnumPacket",
             ],
             vec![
-                "[TopLevel.type] type: Enum (1)",
-                "[_ws.lua.text] EnumPacket",
-                "  [TopLevel.EnumPacket.addition] addition: Custom (22)",
+                "[toplevel.type] type: Enum (1)",
+                "[toplevel._packet_] Packet: EnumPacket",
+                "  [toplevel.EnumPacket.addition] addition: Custom (22)",
+                "[toplevel._packet_chain_] Packet chain: EnumPacket",
             ],
             if tshark_version.contains("TShark (Wireshark) 4.") {
                 vec![
-                    "[TopLevel.type] type: Enum (1)",
-                    "[_ws.lua.text] EnumPacket",
-                    "  [TopLevel.EnumPacket.addition] addition: Other (68)",
+                    "[toplevel.type] type: Enum (1)",
+                    "[toplevel._packet_] Packet: EnumPacket",
+                    "  [toplevel.EnumPacket.addition] addition: Other (68)",
+                    "[toplevel._packet_chain_] Packet chain: EnumPacket",
                 ]
             } else {
                 vec![
-                    "[TopLevel.type] type: Enum (1)",
-                    "[_ws.lua.text] EnumPacket",
-                    "  [TopLevel.EnumPacket.addition] addition: Unknown (68)",
+                    "[toplevel.type] type: Enum (1)",
+                    "[toplevel._packet_] Packet: EnumPacket",
+                    "  [toplevel.EnumPacket.addition] addition: Unknown (68)",
+                    "[toplevel._packet_chain_] Packet chain: EnumPacket",
                 ]
             },
             vec![
-                "[TopLevel.type] type: Group (2)",
-                "[_ws.lua.text] Group_AskBrewHistory",
-                "  [TopLevel.Group_AskBrewHistory.pot] pot: 1",
-                "  [TopLevel.Group_AskBrewHistory.offset] offset: 2",
-                "  [TopLevel.Group_AskBrewHistory.limit] limit: 3",
+                "[toplevel.type] type: Group (2)",
+                "[toplevel._packet_] Packet: Group_AskBrewHistory",
+                "  [toplevel.Group_AskBrewHistory.pot] pot: 1",
+                "  [toplevel.Group_AskBrewHistory.offset] offset: 2",
+                "  [toplevel.Group_AskBrewHistory.limit] limit: 3",
+                "[toplevel._packet_chain_] Packet chain: Group_AskBrewHistory",
             ],
             vec![
-                "[TopLevel.type] type: Unaligned (3)",
-                "[_ws.lua.text] UnalignedPacket",
-                "  [TopLevel.UnalignedPacket.a] 001. .... = a: 1",
-                "  [TopLevel.UnalignedPacket.b] ...0 0000 010. .... = b: 2",
-                "  [TopLevel.UnalignedPacket.c] ...0 11.. = c: 3",
-                "  [TopLevel.UnalignedPacket.d] .... ..10 0... .... = d: 4",
-                "  [TopLevel.UnalignedPacket.e] .101 .... = e: 5",
+                "[toplevel.type] type: Unaligned (3)",
+                "[toplevel._packet_] Packet: UnalignedPacket",
+                "  [toplevel.UnalignedPacket.a] 001. .... = a: 1",
+                "  [toplevel.UnalignedPacket.b] ...0 0000 010. .... = b: 2",
+                "  [toplevel.UnalignedPacket.c] ...0 11.. = c: 3",
+                "  [toplevel.UnalignedPacket.d] .... ..10 0... .... = d: 4",
+                "  [toplevel.UnalignedPacket.e] .101 .... = e: 5",
+                "[toplevel._packet_chain_] Packet chain: UnalignedPacket",
                 "[_ws.expert] Expert Info (Warning/Malformed): Error: 4 undissected bits remaining",
-                "  [_ws.lua.proto.warning] Error: 4 undissected bits remaining",
+                "  [toplevel.expert.trailing_bytes] Error: 4 undissected bits remaining",
                 "  [_ws.expert.message] Message: Error: 4 undissected bits remaining",
                 "  [_ws.expert.severity] Severity level: Warning",
                 "  [_ws.expert.group] Group: Malformed",
             ],
             vec![
-                "[TopLevel.type] type: Checksum (4)",
-                "[_ws.lua.text] ChecksumPacket",
-                "  [TopLevel.ChecksumPacket.a] a: 1",
-                "  [TopLevel.ChecksumPacket.b] b: 2",
-                "  [TopLevel.ChecksumPacket.crc] crc: 0x3412"
+                "[toplevel.type] type: Checksum (4)",
+                "[toplevel._packet_] Packet: ChecksumPacket",
+                "  [toplevel.ChecksumPacket.a] a: 1",
+                "  [toplevel.ChecksumPacket.b] b: 2",
+                "  [toplevel.ChecksumPacket.crc] crc: 0x3412",
+                "[toplevel._packet_chain_] Packet chain: ChecksumPacket",
             ],
             vec![
-                "[TopLevel.type] type: Array (5)",
-                "[_ws.lua.text] Array_Brew",
-                "  [TopLevel.Array_Brew.pots] pots: 18",
-                "  [TopLevel.Array_Brew.pots] pots: 52",
-                "  [TopLevel.Array_Brew.additions] additions: Alcoholic: Whisky (10)",
-                "  [TopLevel.Array_Brew.additions] additions: NonAlcoholic: Cream (1)",
-                "  [TopLevel.Array_Brew.extra_additions] extra_additions: Custom (22)",
-                "  [TopLevel.Array_Brew.extra_additions] extra_additions: Custom (28)",
+                "[toplevel.type] type: Array (5)",
+                "[toplevel._packet_] Packet: Array_Brew",
+                "  [toplevel.Array_Brew.pots] pots: 1234",
+                "  [_ws.lua.text] additions (2 items, 2 bytes)",
+                "    [toplevel.Array_Brew.additions] [0] additions: Alcoholic: Whisky (10)",
+                "    [toplevel.Array_Brew.additions] [1] additions: NonAlcoholic: Cream (1)",
+                "    [toplevel.Array_Brew.additions_item_count] Item count: 2",
+                "  [_ws.lua.text] extra_additions (2 items, 2 bytes)",
+                "    [toplevel.Array_Brew.extra_additions] [0] extra_additions: Custom (22)",
+                "    [toplevel.Array_Brew.extra_additions] [1] extra_additions: Custom (28)",
+                "    [toplevel.Array_Brew.extra_additions_item_count] Item count: 2",
+                "[toplevel._packet_chain_] Packet chain: Array_Brew",
             ],
             vec![
-                "[TopLevel.type] type: Array (5)",
-                "[_ws.lua.text] Array_Brew",
-                "  [TopLevel.Array_Brew.pots] pots: 18",
-                "  [TopLevel.Array_Brew.pots] pots: 52",
-                "  [TopLevel.Array_Brew.additions] additions: Alcoholic: Whisky (10)",
+                "[toplevel.type] type: Array (5)",
+                "[toplevel._packet_] Packet: Array_Brew",
+                "  [toplevel.Array_Brew.pots] pots: 1234",
+                "  [_ws.lua.text] additions (1 item, 1 bytes)",
+                "    [toplevel.Array_Brew.additions] [0] additions: Alcoholic: Whisky (10)",
+                "    [toplevel.Array_Brew.additions_item_count] Item count: 1",
                 "  [_ws.expert] Expert Info (Warning/Malformed): Error: Expected 2 `additions` items but only found 1",
-                "    [_ws.lua.proto.warning] Error: Expected 2 `additions` items but only found 1",
+                "    [toplevel.expert.array_count_short] Error: Expected 2 `additions` items but only found 1",
                 "    [_ws.expert.message] Message: Error: Expected 2 `additions` items but only found 1",
                 "    [_ws.expert.severity] Severity level: Warning",
                 "    [_ws.expert.group] Group: Malformed",
+                "  [_ws.lua.text] extra_additions (0 items, 0 bytes)",
+                "    [toplevel.Array_Brew.extra_additions_item_count] Item count: 0",
+                "[toplevel._packet_chain_] Packet chain: Array_Brew",
             ],
             vec![
-                "[TopLevel.type] type: GroupConstraint (6)",
-                "[_ws.lua.text] GroupConstraint_Packet",
+                "[toplevel.type] type: GroupConstraint (6)",
+                "[toplevel._packet_] Packet: GroupConstraint_Packet",
                 "  [_ws.lua.text] s",
-                "    [TopLevel.GroupConstraint_Packet._fixed_0] Fixed value: 42",
+                "    [toplevel.GroupConstraint_Packet.s._fixed_0] Fixed value: 42",
+                "[toplevel._packet_chain_] Packet chain: GroupConstraint_Packet",
             ],
             vec![
-                "[TopLevel.type] type: GroupConstraint (6)",
-                "[_ws.lua.text] GroupConstraint_Packet",
+                "[toplevel.type] type: GroupConstraint (6)",
+                "[toplevel._packet_] Packet: GroupConstraint_Packet",
                 "  [_ws.lua.text] s",
-                "    [TopLevel.GroupConstraint_Packet._fixed_0] Fixed value: 0",
+                "    [toplevel.GroupConstraint_Packet.s._fixed_0] Fixed value: 0",
                 "      [_ws.expert] Expert Info (Warning/Malformed): Error: Expected `value == 42` where value=0",
-                "        [_ws.lua.proto.warning] Error: Expected `value == 42` where value=0",
+                "        [toplevel.expert.fixed_mismatch] Error: Expected `value == 42` where value=0",
                 "        [_ws.expert.message] Message: Error: Expected `value == 42` where value=0",
                 "        [_ws.expert.severity] Severity level: Warning",
                 "        [_ws.expert.group] Group: Malformed",
+                "[toplevel._packet_chain_] Packet chain: GroupConstraint_Packet",
             ],
             vec![
-                "[TopLevel.type] type: Size_Parent (7)",
-                "[_ws.lua.text] Size_Parent",
-                "  [TopLevel.Size_Parent._payload__size] 11.. .... = Size(Payload): 3",
+                "[toplevel.type] type: Size_Parent (7)",
+                "[toplevel._packet_] Packet: Size_Parent",
+                "  [toplevel.Size_Parent._payload__size] 11.. .... = Size(Payload): 3",
                 &format!(
-                    "  [TopLevel.Size_Parent._payload_] ..00 0000 0100 0000 1000 0000 11.. .... = Payload: {}",
+                    "  [toplevel.Size_Parent._payload_] ..00 0000 0100 0000 1000 0000 11.. .... = Payload: {}",
                     0x010203
                 ),
+                "[toplevel._packet_chain_] Packet chain: Size_Parent",
                 "[_ws.expert] Expert Info (Warning/Malformed): Error: 6 undissected bits remaining",
-                "  [_ws.lua.proto.warning] Error: 6 undissected bits remaining",
+                "  [toplevel.expert.trailing_bytes] Error: 6 undissected bits remaining",
                 "  [_ws.expert.message] Message: Error: 6 undissected bits remaining",
                 "  [_ws.expert.severity] Severity level: Warning",
                 "  [_ws.expert.group] Group: Malformed",
             ],
             vec![
-                "[TopLevel.type] type: Size_Array (8)",
-                "[_ws.lua.text] Size_Brew",
-                "  [TopLevel.Size_Brew.pot] pot: 18",
-                "  [TopLevel.Size_Brew.additions_size] Size(additions): 2",
-                "  [TopLevel.Size_Brew.additions] additions: Alcoholic: Rum (11)",
-                "  [TopLevel.Size_Brew.additions] additions: Custom (24)",
+                "[toplevel.type] type: Size_Array (8)",
+                "[toplevel._packet_] Packet: Size_Brew",
+                "  [toplevel.Size_Brew.pot] pot: 18",
+                "  [toplevel.Size_Brew.additions_size] Size(additions): 2",
+                "  [_ws.lua.text] additions (2 items, 2 bytes)",
+                "    [toplevel.Size_Brew.additions] [0] additions: Alcoholic: Rum (11)",
+                "    [toplevel.Size_Brew.additions] [1] additions: Custom (24)",
+                "    [toplevel.Size_Brew.additions_item_count] Item count: 2",
+                "[toplevel._packet_chain_] Packet chain: Size_Brew",
                 "[_ws.expert] Expert Info (Warning/Malformed): Error: 1 undissected bytes remaining",
-                "  [_ws.lua.proto.warning] Error: 1 undissected bytes remaining",
+                "  [toplevel.expert.trailing_bytes] Error: 1 undissected bytes remaining",
                 "  [_ws.expert.message] Message: Error: 1 undissected bytes remaining",
                 "  [_ws.expert.severity] Severity level: Warning",
                 "  [_ws.expert.group] Group: Malformed",
             ],
             vec![
-                "[TopLevel.type] type: Size_Array (8)",
-                "[_ws.lua.text] Size_Brew",
-                "  [TopLevel.Size_Brew.pot] pot: 18",
-                "  [TopLevel.Size_Brew.additions_size] Size(additions): 3",
+                "[toplevel.type] type: Size_Array (8)",
+                "[toplevel._packet_] Packet: Size_Brew",
+                "  [toplevel.Size_Brew.pot] pot: 18",
+                "  [toplevel.Size_Brew.additions_size] Size(additions): 3",
+                "  [_ws.lua.text] additions (2 items, 2 bytes)",
+                "    [toplevel.Size_Brew.additions] [0] additions: Alcoholic: Rum (11)",
+                "    [toplevel.Size_Brew.additions] [1] additions: Custom (24)",
+                "    [toplevel.Size_Brew.additions_item_count] Item count: 2",
                 "  [_ws.expert] Expert Info (Warning/Malformed): Error: Size(additions) is greater than the number of remaining bytes",
-                "    [_ws.lua.proto.warning] Error: Size(additions) is greater than the number of remaining bytes",
+                "    [toplevel.expert.length_overrun] Error: Size(additions) is greater than the number of remaining bytes",
                 "    [_ws.expert.message] Message: Error: Size(additions) is greater than the number of remaining bytes",
                 "    [_ws.expert.severity] Severity level: Warning",
                 "    [_ws.expert.group] Group: Malformed",
-                "  [TopLevel.Size_Brew.additions] additions: Alcoholic: Rum (11)",
-                "  [TopLevel.Size_Brew.additions] additions: Custom (24)",
+                "[toplevel._packet_chain_] Packet chain: Size_Brew",
             ],
             vec![
-                "[TopLevel.type] type: InheritanceWithoutConstraint (9)",
-                "[_ws.lua.text] AbstractParent",
-                "  [_ws.lua.text] ChildWithoutConstraints",
-                "    [TopLevel.AbstractParent.ChildWithoutConstraints.field] field: 136",
+                "[toplevel.type] type: InheritanceWithoutConstraint (9)",
+                "[toplevel._packet_] Packet: AbstractParent",
+                "  [toplevel.AbstractParent._packet_] Packet: ChildWithoutConstraints",
+                "    [toplevel.AbstractParent.ChildWithoutConstraints.field] field: 136",
+                "[toplevel._packet_chain_] Packet chain: AbstractParent.ChildWithoutConstraints",
             ],
             vec![
-                "[TopLevel.type] type: PayloadWithSizeModifier (10)",
-                "[_ws.lua.text] PayloadWithSizeModifier",
-                "  [TopLevel.PayloadWithSizeModifier.additions_size] Size(additions): 1",
-                "  [TopLevel.PayloadWithSizeModifier.additions] additions: NonAlcoholic: Cream (1)",
-                "  [TopLevel.PayloadWithSizeModifier.additions] additions: Alcoholic: Whisky (10)",
-                "  [TopLevel.PayloadWithSizeModifier.additions] additions: Custom (20)",
+                "[toplevel.type] type: PayloadWithSizeModifier (10)",
+                "[toplevel._packet_] Packet: PayloadWithSizeModifier",
+                "  [toplevel.PayloadWithSizeModifier.additions_size] Size(additions): 1",
+                "  [_ws.lua.text] additions (3 items, 3 bytes)",
+                "    [toplevel.PayloadWithSizeModifier.additions] [0] additions: NonAlcoholic: Cream (1)",
+                "    [toplevel.PayloadWithSizeModifier.additions] [1] additions: Alcoholic: Whisky (10)",
+                "    [toplevel.PayloadWithSizeModifier.additions] [2] additions: Custom (20)",
+                "    [toplevel.PayloadWithSizeModifier.additions_item_count] Item count: 3",
+                "[toplevel._packet_chain_] Packet chain: PayloadWithSizeModifier",
                 "[_ws.expert] Expert Info (Warning/Malformed): Error: 1 undissected bytes remaining",
-                "  [_ws.lua.proto.warning] Error: 1 undissected bytes remaining",
+                "  [toplevel.expert.trailing_bytes] Error: 1 undissected bytes remaining",
                 "  [_ws.expert.message] Message: Error: 1 undissected bytes remaining",
                 "  [_ws.expert.severity] Severity level: Warning",
                 "  [_ws.expert.group] Group: Malformed",
             ],
             vec![
-                "[TopLevel.type] type: Fixed (11)",
-                "[_ws.lua.text] Fixed_Teapot",
-                "  [TopLevel.Fixed_Teapot._fixed_0] Fixed value: 42",
-                "  [TopLevel.Fixed_Teapot._fixed_1] Fixed value: Empty: 0",
+                "[toplevel.type] type: Fixed (11)",
+                "[toplevel._packet_] Packet: Fixed_Teapot",
+                "  [toplevel.Fixed_Teapot._fixed_0] Fixed value: 42",
+                "  [toplevel.Fixed_Teapot._fixed_1] Fixed value: Empty: 0",
+                "[toplevel._packet_chain_] Packet chain: Fixed_Teapot",
             ],
             vec![
-                "[TopLevel.type] type: Fixed (11)",
-                "[_ws.lua.text] Fixed_Teapot",
-                "  [TopLevel.Fixed_Teapot._fixed_0] Fixed value: 80",
+                "[toplevel.type] type: Fixed (11)",
+                "[toplevel._packet_] Packet: Fixed_Teapot",
+                "  [toplevel.Fixed_Teapot._fixed_0] Fixed value: 80",
                 "    [_ws.expert] Expert Info (Warning/Malformed): Error: Expected `value == 42` where value=80",
-                "      [_ws.lua.proto.warning] Error: Expected `value == 42` where value=80",
+                "      [toplevel.expert.fixed_mismatch] Error: Expected `value == 42` where value=80",
                 "      [_ws.expert.message] Message: Error: Expected `value == 42` where value=80",
                 "      [_ws.expert.severity] Severity level: Warning",
                 "      [_ws.expert.group] Group: Malformed",
-                "  [TopLevel.Fixed_Teapot._fixed_1] Fixed value: Empty: 0",
+                "  [toplevel.Fixed_Teapot._fixed_1] Fixed value: Empty: 0",
+                "[toplevel._packet_chain_] Packet chain: Fixed_Teapot",
             ],
             vec![
-                "[TopLevel.type] type: Fixed (11)",
-                "[_ws.lua.text] Fixed_Teapot",
-                "  [TopLevel.Fixed_Teapot._fixed_0] Fixed value: 42",
-                "  [TopLevel.Fixed_Teapot._fixed_1] Fixed value: Empty: 1",
+                "[toplevel.type] type: Fixed (11)",
+                "[toplevel._packet_] Packet: Fixed_Teapot",
+                "  [toplevel.Fixed_Teapot._fixed_0] Fixed value: 42",
+                "  [toplevel.Fixed_Teapot._fixed_1] Fixed value: Empty: 1",
                 "    [_ws.expert] Expert Info (Warning/Malformed): Error: Expected `Enum_CoffeeAddition_enum:match(\"Empty\", value)` where value=1",
-                "      [_ws.lua.proto.warning] Error: Expected `Enum_CoffeeAddition_enum:match(\"Empty\", value)` where value=1",
+                "      [toplevel.expert.fixed_mismatch] Error: Expected `Enum_CoffeeAddition_enum:match(\"Empty\", value)` where value=1",
                 "      [_ws.expert.message] Message: Error: Expected `Enum_CoffeeAddition_enum:match(\"Empty\", value)` where value=1",
                 "      [_ws.expert.severity] Severity level: Warning",
                 "      [_ws.expert.group] Group: Malformed",
+                "[toplevel._packet_chain_] Packet chain: Fixed_Teapot",
             ],
             vec! [
-                "[TopLevel.type] type: Padding (12)",
-                "[_ws.lua.text] Padding_PaddedCoffee",
-                "  [TopLevel.Padding_PaddedCoffee.additions] additions (Padded): NonAlcoholic: Cream (1)",
-                "  [TopLevel.Padding_PaddedCoffee.additions] additions (Padded): Custom (20)",
-                "  [TopLevel.Padding_PaddedCoffee.additions] additions (Padded): Empty (0)",
-                "  [TopLevel.Padding_PaddedCoffee.additions] additions (Padded): Empty (0)",
-                "  [TopLevel.Padding_PaddedCoffee.additions] additions (Padded): Empty (0)",
-                "  [TopLevel.Padding_PaddedCoffee.additions] additions (Padded): Empty (0)",
-                "  [TopLevel.Padding_PaddedCoffee.additions] additions (Padded): Empty (0)",
-                "  [TopLevel.Padding_PaddedCoffee.additions] additions (Padded): Empty (0)",
-                "  [TopLevel.Padding_PaddedCoffee.additions] additions (Padded): Empty (0)",
-                "  [TopLevel.Padding_PaddedCoffee.additions] additions (Padded): Empty (0)",
+                "[toplevel.type] type: Padding (12)",
+                "[toplevel._packet_] Packet: Padding_PaddedCoffee",
+                "  [_ws.lua.text] additions (Padded) (10 items, 10 bytes)",
+                "    [toplevel.Padding_PaddedCoffee.additions] [0] additions (Padded): NonAlcoholic: Cream (1)",
+                "    [toplevel.Padding_PaddedCoffee.additions] [1] additions (Padded): Custom (20)",
+                "    [toplevel.Padding_PaddedCoffee.additions] [2] additions (Padded): Empty (0)",
+                "    [toplevel.Padding_PaddedCoffee.additions] [3] additions (Padded): Empty (0)",
+                "    [toplevel.Padding_PaddedCoffee.additions] [4] additions (Padded): Empty (0)",
+                "    [toplevel.Padding_PaddedCoffee.additions] [5] additions (Padded): Empty (0)",
+                "    [toplevel.Padding_PaddedCoffee.additions] [6] additions (Padded): Empty (0)",
+                "    [toplevel.Padding_PaddedCoffee.additions] [7] additions (Padded): Empty (0)",
+                "    [toplevel.Padding_PaddedCoffee.additions] [8] additions (Padded): Empty (0)",
+                "    [toplevel.Padding_PaddedCoffee.additions] [9] additions (Padded): Empty (0)",
+                "    [toplevel.Padding_PaddedCoffee.additions_item_count] Item count: 10",
+                "[toplevel._packet_chain_] Packet chain: Padding_PaddedCoffee",
             ],
             vec![
-                "[TopLevel.type] type: Padding (12)",
-                "[_ws.lua.text] Padding_PaddedCoffee",
-                "  [TopLevel.Padding_PaddedCoffee.additions] additions (Padded): NonAlcoholic: Cream (1)",
-                "  [TopLevel.Padding_PaddedCoffee.additions] additions (Padded): Custom (20)",
+                "[toplevel.type] type: Padding (12)",
+                "[toplevel._packet_] Packet: Padding_PaddedCoffee",
+                "  [_ws.lua.text] additions (Padded) (2 items, 2 bytes)",
+                "    [toplevel.Padding_PaddedCoffee.additions] [0] additions (Padded): NonAlcoholic: Cream (1)",
+                "    [toplevel.Padding_PaddedCoffee.additions] [1] additions (Padded): Custom (20)",
+                "    [toplevel.Padding_PaddedCoffee.additions_item_count] Item count: 2",
                 "  [_ws.expert] Expert Info (Warning/Malformed): Error: Expected a minimum of 10 octets in field `additions (Padded)`",
-                "    [_ws.lua.proto.warning] Error: Expected a minimum of 10 octets in field `additions (Padded)`",
+                "    [toplevel.expert.padding_short] Error: Expected a minimum of 10 octets in field `additions (Padded)`",
                 "    [_ws.expert.message] Message: Error: Expected a minimum of 10 octets in field `additions (Padded)`",
                 "    [_ws.expert.severity] Severity level: Warning",
                 "    [_ws.expert.group] Group: Malformed",
+                "[toplevel._packet_chain_] Packet chain: Padding_PaddedCoffee",
             ],
             vec![
-                "[TopLevel.type] type: Reserved (13)",
-                "[_ws.lua.text] Reserved_DeloreanCoffee",
-                "  [TopLevel.Reserved_DeloreanCoffee._reserved_0] 0000 0001 0000 0010 0000 .... = Reserved: 4128",
+                "[toplevel.type] type: Reserved (13)",
+                "[toplevel._packet_] Packet: Reserved_DeloreanCoffee",
+                "  [toplevel.Reserved_DeloreanCoffee._reserved_0] 0000 0001 0000 0010 0000 .... = Reserved: 4128",
+                "[toplevel._packet_chain_] Packet chain: Reserved_DeloreanCoffee",
                 "[_ws.expert] Expert Info (Warning/Malformed): Error: 4 undissected bits remaining",
-                "  [_ws.lua.proto.warning] Error: 4 undissected bits remaining",
+                "  [toplevel.expert.trailing_bytes] Error: 4 undissected bits remaining",
                 "  [_ws.expert.message] Message: Error: 4 undissected bits remaining",
                 "  [_ws.expert.severity] Severity level: Warning",
                 "  [_ws.expert.group] Group: Malformed",
             ],
             vec![
-                "[TopLevel.type] type: Optional (14)",
-                "[_ws.lua.text] Optional_CoffeeWithAdditions",
-                "  [TopLevel.Optional_CoffeeWithAdditions.want_sugar] 1... .... = want_sugar: 1",
-                "  [TopLevel.Optional_CoffeeWithAdditions.want_cream] .1.. .... = want_cream: 1",
-                "  [TopLevel.Optional_CoffeeWithAdditions.want_alcohol] ..1. .... = want_alcohol: 1",
-                "  [TopLevel.Optional_CoffeeWithAdditions._reserved_0] ...0 0000 = Reserved: 0",
-                &format!("  [TopLevel.Optional_CoffeeWithAdditions.sugar] sugar: {}", 0x3344),
+                "[toplevel.type] type: Optional (14)",
+                "[toplevel._packet_] Packet: Optional_CoffeeWithAdditions",
+                "  [toplevel.Optional_CoffeeWithAdditions.want_sugar] 1... .... = want_sugar: Set (sugar present)",
+                "  [toplevel.Optional_CoffeeWithAdditions.want_cream] .1.. .... = want_cream: Set (cream present)",
+                "  [toplevel.Optional_CoffeeWithAdditions.want_alcohol] ..1. .... = want_alcohol: Set (alcohol present)",
+                "  [toplevel.Optional_CoffeeWithAdditions._reserved_0] ...0 0000 = Reserved: 0",
+                &format!("  [toplevel.Optional_CoffeeWithAdditions.sugar] sugar: {}", 0x3344),
                 "  [_ws.lua.text] cream",
-                "    [TopLevel.Optional_CoffeeWithAdditions.fat_percentage] fat_percentage: 2",
-                "  [TopLevel.Optional_CoffeeWithAdditions.alcohol] alcohol: WHISKY (0)",
+                "    [toplevel.Optional_CoffeeWithAdditions.cream.fat_percentage] fat_percentage: 2",
+                "  [toplevel.Optional_CoffeeWithAdditions.alcohol] alcohol: WHISKY (0)",
+                "[toplevel._packet_chain_] Packet chain: Optional_CoffeeWithAdditions",
             ],
             vec![
-                "[TopLevel.type] type: Optional (14)",
-                "[_ws.lua.text] Optional_CoffeeWithAdditions",
-                "  [TopLevel.Optional_CoffeeWithAdditions.want_sugar] 0... .... = want_sugar: 0",
-                "  [TopLevel.Optional_CoffeeWithAdditions.want_cream] .0.. .... = want_cream: 0",
-                "  [TopLevel.Optional_CoffeeWithAdditions.want_alcohol] ..1. .... = want_alcohol: 1",
-                "  [TopLevel.Optional_CoffeeWithAdditions._reserved_0] ...0 0000 = Reserved: 0",
-                "  [TopLevel.Optional_CoffeeWithAdditions.alcohol] alcohol: WHISKY (0)",
+                "[toplevel.type] type: Optional (14)",
+                "[toplevel._packet_] Packet: Optional_CoffeeWithAdditions",
+                "  [toplevel.Optional_CoffeeWithAdditions.want_sugar] 0... .... = want_sugar: Not set (sugar absent)",
+                "  [toplevel.Optional_CoffeeWithAdditions.want_cream] .0.. .... = want_cream: Not set (cream absent)",
+                "  [toplevel.Optional_CoffeeWithAdditions.want_alcohol] ..1. .... = want_alcohol: Set (alcohol present)",
+                "  [toplevel.Optional_CoffeeWithAdditions._reserved_0] ...0 0000 = Reserved: 0",
+                "  [toplevel.Optional_CoffeeWithAdditions.alcohol] alcohol: WHISKY (0)",
+                "[toplevel._packet_chain_] Packet chain: Optional_CoffeeWithAdditions",
             ],
             vec![
-                "[TopLevel.type] type: Optional (14)",
-                "[_ws.lua.text] Optional_CoffeeWithAdditions",
-                "  [TopLevel.Optional_CoffeeWithAdditions.want_sugar] 1... .... = want_sugar: 1",
-                "  [TopLevel.Optional_CoffeeWithAdditions.want_cream] .0.. .... = want_cream: 0",
-                "  [TopLevel.Optional_CoffeeWithAdditions.want_alcohol] ..1. .... = want_alcohol: 1",
-                "  [TopLevel.Optional_CoffeeWithAdditions._reserved_0] ...0 0000 = Reserved: 0",
-                &format!("  [TopLevel.Optional_CoffeeWithAdditions.sugar] sugar: {}", 0x3344),
-                "  [TopLevel.Optional_CoffeeWithAdditions.alcohol] alcohol: COGNAC (1)",
+                "[toplevel.type] type: Optional (14)",
+                "[toplevel._packet_] Packet: Optional_CoffeeWithAdditions",
+                "  [toplevel.Optional_CoffeeWithAdditions.want_sugar] 1... .... = want_sugar: Set (sugar present)",
+                "  [toplevel.Optional_CoffeeWithAdditions.want_cream] .0.. .... = want_cream: Not set (cream absent)",
+                "  [toplevel.Optional_CoffeeWithAdditions.want_alcohol] ..1. .... = want_alcohol: Set (alcohol present)",
+                "  [toplevel.Optional_CoffeeWithAdditions._reserved_0] ...0 0000 = Reserved: 0",
+                &format!("  [toplevel.Optional_CoffeeWithAdditions.sugar] sugar: {}", 0x3344),
+                "  [toplevel.Optional_CoffeeWithAdditions.alcohol] alcohol: COGNAC (1)",
+                "[toplevel._packet_chain_] Packet chain: Optional_CoffeeWithAdditions",
             ],
             vec![
-                "[TopLevel.type] type: UnalignedEnum (15)",
-                "[_ws.lua.text] UnalignedEnum_packet",
-                "  [TopLevel.UnalignedEnum_packet.enum1] 001. .... = enum1: A (1)",
-                "  [TopLevel.UnalignedEnum_packet.enum2] ...0 10.. = enum2: B (2)",
-                "  [TopLevel.UnalignedEnum_packet.enum3] .... ..01 1... .... = enum3: C (3)",
+                "[toplevel.type] type: UnalignedEnum (15)",
+                "[toplevel._packet_] Packet: UnalignedEnum_packet",
+                "  [toplevel.UnalignedEnum_packet.enum1] 001. .... = enum1: A (1)",
+                "  [toplevel.UnalignedEnum_packet.enum2] ...0 10.. = enum2: B (2)",
+                "  [toplevel.UnalignedEnum_packet.enum3] .... ..01 1... .... = enum3: C (3)",
+                "[toplevel._packet_chain_] Packet chain: UnalignedEnum_packet",
                 "[_ws.expert] Expert Info (Warning/Malformed): Error: 7 undissected bits remaining",
-                "  [_ws.lua.proto.warning] Error: 7 undissected bits remaining",
+                "  [toplevel.expert.trailing_bytes] Error: 7 undissected bits remaining",
                 "  [_ws.expert.message] Message: Error: 7 undissected bits remaining",
                 "  [_ws.expert.severity] Severity level: Warning",
                 "  [_ws.expert.group] Group: Malformed",